@@ -11,6 +11,7 @@ import androidx.activity.result.ActivityResultLauncher;
 import androidx.activity.result.contract.ActivityResultContracts;
 import androidx.appcompat.app.AppCompatActivity;
 import android.content.Intent;
+import android.content.SharedPreferences;
 import android.net.Uri;
 import android.database.Cursor;
 import android.provider.DocumentsContract;
@@ -18,12 +19,19 @@ import java.io.File;
 import java.io.FileOutputStream;
 import java.io.InputStream;
 import java.io.OutputStream;
+import java.nio.charset.StandardCharsets;
+import java.security.MessageDigest;
+import java.security.NoSuchAlgorithmException;
+import java.security.SecureRandom;
+import org.json.JSONArray;
+import org.json.JSONException;
+import org.json.JSONObject;
 
 public class MainActivity extends AppCompatActivity {
     private TextView statusText, logText;
     private ProgressBar progressBar;
     private Button startButton, stopButton, testDbButton, selectDbButton;
-    private EditText dbPathEdit, portEdit;
+    private EditText dbPathEdit, portEdit, dbKeyEdit;
     
     private ActivityResultLauncher<Intent> filePickerLauncher;
     
@@ -53,6 +61,7 @@ public class MainActivity extends AppCompatActivity {
         
         dbPathEdit = findViewById(R.id.dbPathEdit);
         portEdit = findViewById(R.id.portEdit);
+        dbKeyEdit = findViewById(R.id.dbKeyEdit);
         
         startButton.setOnClickListener(v -> startServer());
         stopButton.setOnClickListener(v -> stopServer());
@@ -117,28 +126,80 @@ public class MainActivity extends AppCompatActivity {
     }
     
     private void startServer() {
-        String config = String.format(
-            "{"db_path":"%s","port":%s}",
-            dbPathEdit.getText().toString(),
-            portEdit.getText().toString()
-        );
+        String config;
+        try {
+            JSONObject json = new JSONObject();
+            json.put("db_path", dbPathEdit.getText().toString());
+            json.put("port", Integer.parseInt(portEdit.getText().toString().trim()));
+            // 服务只接受带密钥的请求；配置中只传摘要
+            JSONObject appKey = new JSONObject();
+            appKey.put("id", "app");
+            appKey.put("sha256", sha256Hex(storedSecret("api_key")));
+            appKey.put("scopes", new JSONArray().put("read").put("write"));
+            json.put("api_keys", new JSONArray().put(appKey));
+            json.put("audit_secret", storedSecret("audit_secret"));
+            // 密钥只随本次启动传给服务，不保存
+            String dbKey = dbKeyEdit.getText().toString();
+            if (!dbKey.isEmpty()) {
+                json.put("db_key", dbKey);
+            }
+            config = json.toString();
+        } catch (NumberFormatException | JSONException e) {
+            appendLog("端口无效: " + portEdit.getText());
+            return;
+        }
         
+        // 启动完成前不允许再次启动，状态刷新后按结果恢复
+        startButton.setEnabled(false);
         progressBar.setVisibility(View.VISIBLE);
         new Thread(() -> {
-            String result = startServer(config);
+            JSONObject result = parseResult(startServer(config));
             runOnUiThread(() -> {
                 progressBar.setVisibility(View.GONE);
-                appendLog(result);
+                appendResult(result);
+                if (result.optBoolean("ok")) {
+                    appendLog("接口密钥: " + storedSecret("api_key") + "（请求头 Authorization: Bearer <密钥>）");
+                }
                 updateServerStatus();
             });
         }).start();
     }
     
+    // 接口密钥和审计摘要密钥在首次使用时随机生成并保存，之后每次启动沿用
+    private String storedSecret(String name) {
+        SharedPreferences prefs = getSharedPreferences("server", MODE_PRIVATE);
+        String secret = prefs.getString(name, null);
+        if (secret == null) {
+            byte[] bytes = new byte[32];
+            new SecureRandom().nextBytes(bytes);
+            secret = toHex(bytes);
+            prefs.edit().putString(name, secret).apply();
+        }
+        return secret;
+    }
+    
+    private static String sha256Hex(String value) {
+        try {
+            MessageDigest digest = MessageDigest.getInstance("SHA-256");
+            return toHex(digest.digest(value.getBytes(StandardCharsets.UTF_8)));
+        } catch (NoSuchAlgorithmException e) {
+            throw new IllegalStateException("SHA-256 unavailable", e);
+        }
+    }
+    
+    private static String toHex(byte[] bytes) {
+        StringBuilder hex = new StringBuilder();
+        for (byte b : bytes) {
+            hex.append(String.format("%02x", b));
+        }
+        return hex.toString();
+    }
+    
     private void stopServer() {
         new Thread(() -> {
-            String result = stopServer();
+            JSONObject result = parseResult(stopServer());
             runOnUiThread(() -> {
-                appendLog(result);
+                appendResult(result);
                 updateServerStatus();
             });
         }).start();
@@ -149,25 +210,59 @@ public class MainActivity extends AppCompatActivity {
         progressBar.setVisibility(View.VISIBLE);
         
         new Thread(() -> {
-            String result = testDatabase(dbPath);
+            JSONObject result = parseResult(testDatabase(dbPath));
             runOnUiThread(() -> {
                 progressBar.setVisibility(View.GONE);
-                appendLog(result);
+                appendResult(result);
+                JSONObject report = result.optJSONObject("data");
+                if (report != null) {
+                    appendLog("记录数: " + report.optString("record_count") + ", 完整性: " + report.optJSONObject("integrity").optBoolean("ok"));
+                }
             });
         }).start();
     }
     
     private void updateServerStatus() {
         new Thread(() -> {
-            String status = getServerStatus();
+            JSONObject result = parseResult(getServerStatus());
+            String status = result.optString("code");
+            JSONObject data = result.optJSONObject("data");
+            String detail = "";
+            if (data != null && !data.isNull("address")) {
+                detail = " (" + data.optString("address") + ", 请求数 " + data.optJSONObject("requests").optLong("total") + ")";
+            } else if (data != null && !data.isNull("last_error")) {
+                detail = " (" + data.optString("last_error") + ")";
+            }
+            String text = "服务状态: " + status + detail;
             runOnUiThread(() -> {
-                statusText.setText("服务状态: " + status);
-                startButton.setEnabled("stopped".equals(status));
+                statusText.setText(text);
+                startButton.setEnabled("stopped".equals(status) || "failed".equals(status));
                 stopButton.setEnabled("running".equals(status));
             });
         }).start();
     }
     
+    // 解析 JNI 返回的 {"ok","code","message","data"} 结果
+    private JSONObject parseResult(String json) {
+        try {
+            return new JSONObject(json);
+        } catch (JSONException e) {
+            JSONObject fallback = new JSONObject();
+            try {
+                fallback.put("ok", false);
+                fallback.put("code", "invalid_response");
+                fallback.put("message", json);
+            } catch (JSONException ignored) {
+            }
+            return fallback;
+        }
+    }
+    
+    private void appendResult(JSONObject result) {
+        String prefix = result.optBoolean("ok") ? "" : "[" + result.optString("code") + "] ";
+        appendLog(prefix + result.optString("message"));
+    }
+    
     private void appendLog(String message) {
         runOnUiThread(() -> {
             String timestamp = new java.text.SimpleDateFormat("HH:mm:ss").format(new java.util.Date());
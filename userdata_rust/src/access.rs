@@ -0,0 +1,107 @@
+//! 基于角色的字段权限：角色决定调用方能按哪些字段查询、能看到哪些字段。
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+use crate::auth::ApiKey;
+use crate::db::{UserInfo, LOOKUP_FIELDS};
+use crate::error::ApiError;
+use crate::masking::Masking;
+
+/// 配置中的角色定义，字段名为 `email`、`phone`、`qq`。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Role {
+    /// 允许作为查询条件的字段
+    pub filterable: Vec<String>,
+    /// 返回结果中包含的字段，也只有这些字段可以写入
+    pub visible: Vec<String>,
+    /// 可见字段的脱敏方式
+    pub masking: Masking,
+}
+
+impl Role {
+    /// 返回角色中不属于查询字段的名称。
+    pub fn unknown_fields(&self) -> Vec<&str> {
+        self.filterable
+            .iter()
+            .chain(&self.visible)
+            .map(String::as_str)
+            .filter(|field| !LOOKUP_FIELDS.contains(field))
+            .collect()
+    }
+}
+
+/// 一次请求的字段权限，由认证结果和角色配置得出。
+pub(crate) struct Access {
+    role: Option<String>,
+    filterable: Vec<&'static str>,
+    visible: Vec<&'static str>,
+    masking: Masking,
+}
+
+impl Access {
+    /// 未设置角色的密钥可以使用全部字段；密钥上的脱敏方式优先于角色。
+    pub fn resolve(key: &ApiKey, roles: &BTreeMap<String, Role>) -> Self {
+        let key_masking = key.masking;
+        let Some((name, role)) = key.role.as_ref().and_then(|name| roles.get_key_value(name)) else {
+            return Access {
+                role: None,
+                filterable: LOOKUP_FIELDS.to_vec(),
+                visible: LOOKUP_FIELDS.to_vec(),
+                masking: key_masking.unwrap_or_default(),
+            };
+        };
+        let allowed = |fields: &[String]| {
+            LOOKUP_FIELDS
+                .into_iter()
+                .filter(|field| fields.iter().any(|f| f == field))
+                .collect()
+        };
+        Access {
+            role: Some(name.clone()),
+            filterable: allowed(&role.filterable),
+            visible: allowed(&role.visible),
+            masking: key_masking.unwrap_or(role.masking),
+        }
+    }
+
+    pub fn check_filters(&self, filters: &[(&'static str, &str)]) -> Result<(), ApiError> {
+        match filters.iter().find(|(field, _)| !self.filterable.contains(field)) {
+            Some((field, _)) => Err(ApiError::new(
+                403,
+                "forbidden_filter",
+                format!("Role '{}' may not filter on {}", self.role.as_deref().unwrap_or_default(), field),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// 写操作只能涉及可见字段，避免修改或清除调用方看不到的数据。
+    pub fn check_writes(&self, fields: &[&'static str]) -> Result<(), ApiError> {
+        match fields.iter().find(|field| !self.visible.contains(field)) {
+            Some(field) => Err(ApiError::new(
+                403,
+                "forbidden_field",
+                format!("Role '{}' may not write {}", self.role.as_deref().unwrap_or_default(), field),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// 只保留可见字段并按脱敏方式处理；不可见字段直接省略，而不是返回 null。
+    pub fn project(&self, user: &UserInfo) -> Value {
+        let mut fields = Map::new();
+        if let Some(id) = user.id {
+            fields.insert("id".to_string(), id.into());
+        }
+        for (name, value) in [("email", &user.email), ("phone", &user.phone), ("qq", &user.qq)] {
+            if self.visible.contains(&name) {
+                let value = value.as_deref().map(|value| self.masking.apply(name, value));
+                fields.insert(name.to_string(), value.into());
+            }
+        }
+        Value::Object(fields)
+    }
+}
@@ -0,0 +1,170 @@
+use log::{error, info, LevelFilter};
+use std::sync::Mutex;
+use once_cell::sync::Lazy;
+use jni::{JNIEnv, objects::{JClass, JString}, sys::jstring};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::config::{Secret, ServerConfig};
+use crate::health;
+use crate::server::{Server, ServerHandle};
+use crate::status::{Lifecycle, ServerMonitor, ServerStatus};
+
+// 当前服务句柄；启动和停止期间一直持有锁，避免并发调用交错
+static SERVER: Lazy<Mutex<Option<ServerHandle>>> = Lazy::new(|| Mutex::new(None));
+
+// 状态查询不经过 SERVER 锁，停止过程中也能立即返回
+static STATUS: Lazy<Mutex<StatusSource>> = Lazy::new(|| Mutex::new(StatusSource::Idle(None)));
+
+enum StatusSource {
+    /// 没有启动过服务，或上次启动失败（附带原因）
+    Idle(Option<String>),
+    Server(ServerMonitor),
+}
+
+fn current_status() -> ServerStatus {
+    match &*STATUS.lock().unwrap() {
+        StatusSource::Idle(reason) => ServerStatus::idle(reason.clone()),
+        StatusSource::Server(monitor) => monitor.status(),
+    }
+}
+
+/// 所有 JNI 导出函数统一返回的 JSON 结果
+#[derive(Serialize)]
+struct JniResult {
+    ok: bool,
+    /// 机器可读的结果码，界面据此区分不同情况
+    code: &'static str,
+    message: String,
+    #[serde(skip_serializing_if = "Value::is_null")]
+    data: Value,
+}
+
+impl JniResult {
+    fn ok(code: &'static str, message: impl Into<String>) -> Self {
+        JniResult { ok: true, code, message: message.into(), data: Value::Null }
+    }
+
+    fn err(code: &'static str, message: impl Into<String>) -> Self {
+        JniResult { ok: false, code, message: message.into(), data: Value::Null }
+    }
+
+    fn with_data(mut self, data: impl Serialize) -> Self {
+        self.data = serde_json::to_value(data).unwrap_or_default();
+        self
+    }
+
+    fn into_jstring(self, env: &JNIEnv) -> jstring {
+        let json = serde_json::to_string(&self).unwrap_or_default();
+        env.new_string(json).unwrap().into_raw()
+    }
+}
+
+fn read_string(env: &mut JNIEnv, value: &JString) -> Option<String> {
+    env.get_string(value).ok().map(|java_str| java_str.to_string_lossy().to_string())
+}
+
+#[no_mangle]
+pub extern "C" fn Java_com_example_userdata_rust_MainActivity_startServer(
+    mut env: JNIEnv,
+    _class: JClass,
+    config_json: JString,
+) -> jstring {
+    android_logger::init_once(
+        android_logger::Config::default()
+            .with_max_level(LevelFilter::Info)
+            .with_tag("UserDataRust"),
+    );
+
+    let result = match read_string(&mut env, &config_json) {
+        Some(config_str) => start_server(&config_str),
+        None => JniResult::err("invalid_argument", "Invalid config string"),
+    };
+    result.into_jstring(&env)
+}
+
+fn start_server(config_str: &str) -> JniResult {
+    let mut server = SERVER.lock().unwrap();
+    if server.as_ref().is_some_and(|handle| handle.is_running()) {
+        return JniResult::err("already_running", "Server is already running");
+    }
+
+    let config: ServerConfig = match serde_json::from_str(config_str) {
+        Ok(c) => c,
+        Err(e) => return JniResult::err("invalid_config", format!("Invalid config: {}", e)),
+    };
+
+    info!("Starting server with db {}", config.db_path);
+    // 启动时可能要执行迁移，先切换状态来源，期间查询得到 starting 而不是上一次的状态
+    let monitor = ServerMonitor::new();
+    *STATUS.lock().unwrap() = StatusSource::Server(monitor.clone());
+    match Server::start_with_monitor(config, monitor) {
+        Ok(handle) => {
+            let address = handle.local_addr();
+            let status = handle.status();
+            *server = Some(handle);
+            JniResult::ok("started", format!("Server listening on {}", address)).with_data(status)
+        }
+        Err(e) => {
+            error!("{}", e);
+            *STATUS.lock().unwrap() = StatusSource::Idle(Some(e.to_string()));
+            JniResult::err(e.code(), e.to_string())
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn Java_com_example_userdata_rust_MainActivity_stopServer(
+    env: JNIEnv,
+    _class: JClass,
+) -> jstring {
+    let mut server = SERVER.lock().unwrap();
+    let mut handle = match server.take() {
+        Some(handle) if handle.is_running() => handle,
+        _ => return JniResult::err("not_running", "Server is not running").into_jstring(&env),
+    };
+
+    let result = match handle.stop() {
+        Lifecycle::Failed(reason) => JniResult::err("stop_failed", reason),
+        _ => JniResult::ok("stopped", "Server stopped"),
+    };
+    result.with_data(handle.status()).into_jstring(&env)
+}
+
+#[no_mangle]
+pub extern "C" fn Java_com_example_userdata_rust_MainActivity_getServerStatus(
+    env: JNIEnv,
+    _class: JClass,
+) -> jstring {
+    let status = current_status();
+    let name = status.lifecycle.name();
+    JniResult::ok(name, format!("Server is {}", name)).with_data(status).into_jstring(&env)
+}
+
+#[no_mangle]
+pub extern "C" fn Java_com_example_userdata_rust_MainActivity_testDatabase(
+    mut env: JNIEnv,
+    _class: JClass,
+    db_path: JString,
+) -> jstring {
+    let result = match read_string(&mut env, &db_path) {
+        Some(path_str) => test_database(&path_str),
+        None => JniResult::err("invalid_argument", "Invalid path string"),
+    };
+    result.into_jstring(&env)
+}
+
+fn test_database(path_str: &str) -> JniResult {
+    // 服务运行中时使用其表结构映射和数据库密钥，否则按默认表结构检查
+    let (schema, key) = SERVER
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|handle| (handle.config().schema.clone(), handle.config().db_key.clone()))
+        .unwrap_or_default();
+    match health::check_path(path_str, key.as_ref().map(Secret::expose), &schema, false) {
+        Ok(report) if report.ok => JniResult::ok("healthy", "Database OK").with_data(report),
+        Ok(report) => JniResult::err("unhealthy", "Database check failed").with_data(report),
+        Err(e) => JniResult::err("open_failed", format!("Cannot open database: {}", e)),
+    }
+}
@@ -0,0 +1,237 @@
+//! 防篡改的访问审计日志。
+//!
+//! 每条记录保存上一条记录的摘要，并对自身内容（含上一条摘要）计算 SHA-256，
+//! 修改或删除中间任意一条都会使后续校验失败。
+//!
+//! 查询值只以 HMAC-SHA256 摘要保存，密钥来自配置而不在日志文件中：手机号、QQ 号取值范围小，
+//! 与日志存放在一起的盐值挡不住穷举。
+
+use hmac::{Hmac, Mac};
+use rusqlite::{params, Connection, OpenFlags, OptionalExtension};
+use serde::Serialize;
+use serde_json::json;
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use crate::auth::hash_key;
+use crate::config::{Secret, ServerConfig};
+use crate::db;
+
+/// 第一条记录的 `prev_hash`
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// 由请求处理过程填写的审计内容；`route` 为空表示该请求不需要审计。
+#[derive(Debug, Default)]
+pub(crate) struct AuditEvent {
+    pub key_id: Option<String>,
+    pub method: String,
+    pub route: Option<&'static str>,
+    pub fields: Vec<(&'static str, String)>,
+    pub result_count: Option<usize>,
+    /// 写操作在提交前已写入审计记录，请求结束时不再重复记录
+    pub recorded: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditEntry {
+    pub seq: i64,
+    pub timestamp: String,
+    pub key_id: Option<String>,
+    pub method: String,
+    /// 路由模板，例如 `/api/v1/users/{field}/{value}`，不含查询值
+    pub route: String,
+    /// 字段名到查询值摘要的 JSON 对象；未配置 `audit_secret` 时摘要为 null
+    pub fields: String,
+    pub result_count: Option<i64>,
+    pub status: u16,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl AuditEntry {
+    fn compute_hash(&self) -> String {
+        let content = json!([
+            self.seq,
+            self.timestamp,
+            self.key_id,
+            self.method,
+            self.route,
+            self.fields,
+            self.result_count,
+            self.status,
+            self.prev_hash,
+        ]);
+        hash_key(&content.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Verification {
+    pub ok: bool,
+    pub entries: usize,
+    /// 最后一条记录的摘要，可另行保存用于发现尾部被截断
+    pub last_hash: String,
+    /// 第一条校验失败的记录
+    pub first_invalid_seq: Option<i64>,
+}
+
+/// 审计日志只有一个写连接：每个需要审计的请求在响应前都要在锁内提交一次事务，
+/// 查询本身仍并行执行，但审计写入按请求串行。这是“记录写不进去就不返回数据”的代价，
+/// 因此不做批量写入；数据库使用 WAL 模式以降低每次提交的开销。
+pub struct AuditLog {
+    conn: Mutex<Connection>,
+    secret: Option<Secret>,
+}
+
+impl AuditLog {
+    /// 按配置打开审计数据库：使用与用户数据库相同的 `db_key` 加密，用 `audit_secret` 计算摘要。
+    pub fn open_for(config: &ServerConfig) -> rusqlite::Result<Self> {
+        Self::open(
+            &Self::path_for(config),
+            config.db_key.as_ref().map(Secret::expose),
+            config.audit_secret.clone(),
+        )
+    }
+
+    /// 打开（必要时创建）审计数据库。
+    pub fn open(path: &str, key: Option<&str>, secret: Option<Secret>) -> rusqlite::Result<Self> {
+        let conn = db::open_connection(path, OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE, key)?;
+        conn.query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS audit_log (
+                 seq INTEGER PRIMARY KEY,
+                 timestamp TEXT NOT NULL,
+                 key_id TEXT,
+                 method TEXT NOT NULL,
+                 route TEXT NOT NULL,
+                 fields TEXT NOT NULL,
+                 result_count INTEGER,
+                 status INTEGER NOT NULL,
+                 prev_hash TEXT NOT NULL,
+                 hash TEXT NOT NULL
+             );",
+        )?;
+        Ok(AuditLog {
+            conn: Mutex::new(conn),
+            secret,
+        })
+    }
+
+    /// 审计数据库路径，未配置时放在用户数据库旁边。
+    pub fn path_for(config: &ServerConfig) -> String {
+        config
+            .audit_db_path
+            .clone()
+            .unwrap_or_else(|| format!("{}.audit", config.db_path))
+    }
+
+    pub(crate) fn append(&self, event: &AuditEvent, status: u16) -> rusqlite::Result<()> {
+        let Some(route) = event.route else {
+            return Ok(());
+        };
+        let fields = event
+            .fields
+            .iter()
+            .map(|(field, value)| (*field, self.hash_value(field, value)))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let tx = conn.transaction()?;
+        let (seq, prev_hash) = tx
+            .query_row("SELECT seq, hash FROM audit_log ORDER BY seq DESC LIMIT 1", [], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })
+            .optional()?
+            .unwrap_or((0, GENESIS_HASH.to_string()));
+        let mut entry = AuditEntry {
+            seq: seq + 1,
+            timestamp: tx.query_row("SELECT strftime('%Y-%m-%dT%H:%M:%fZ', 'now')", [], |row| row.get(0))?,
+            key_id: event.key_id.clone(),
+            method: event.method.clone(),
+            route: route.to_string(),
+            fields: serde_json::to_string(&fields).unwrap_or_default(),
+            result_count: event.result_count.map(|n| n as i64),
+            status,
+            prev_hash,
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash();
+        tx.execute(
+            "INSERT INTO audit_log (seq, timestamp, key_id, method, route, fields, result_count, status, prev_hash, hash)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                entry.seq,
+                entry.timestamp,
+                entry.key_id,
+                entry.method,
+                entry.route,
+                entry.fields,
+                entry.result_count,
+                entry.status,
+                entry.prev_hash,
+                entry.hash,
+            ],
+        )?;
+        tx.commit()
+    }
+
+    /// 计算查询值的摘要，可用于在审计记录中查找某个值；未配置密钥时返回 `None`。
+    pub fn hash_value(&self, field: &str, value: &str) -> Option<String> {
+        let secret = self.secret.as_ref()?;
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.expose().as_bytes()).expect("HMAC accepts any key length");
+        mac.update(format!("{}:{}", field, value).as_bytes());
+        Some(mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect())
+    }
+
+    /// 按顺序返回 `seq` 大于 `after` 的记录。
+    pub fn entries(&self, after: i64, limit: usize) -> rusqlite::Result<Vec<AuditEntry>> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let mut stmt = conn.prepare(
+            "SELECT seq, timestamp, key_id, method, route, fields, result_count, status, prev_hash, hash
+             FROM audit_log WHERE seq > ?1 ORDER BY seq LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![after, limit as i64], |row| {
+            Ok(AuditEntry {
+                seq: row.get(0)?,
+                timestamp: row.get(1)?,
+                key_id: row.get(2)?,
+                method: row.get(3)?,
+                route: row.get(4)?,
+                fields: row.get(5)?,
+                result_count: row.get(6)?,
+                status: row.get(7)?,
+                prev_hash: row.get(8)?,
+                hash: row.get(9)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// 从头校验整条哈希链。
+    pub fn verify(&self) -> rusqlite::Result<Verification> {
+        let mut verification = Verification {
+            ok: true,
+            entries: 0,
+            last_hash: GENESIS_HASH.to_string(),
+            first_invalid_seq: None,
+        };
+        let mut after = 0;
+        loop {
+            let batch = self.entries(after, 1000)?;
+            let Some(last) = batch.last() else {
+                return Ok(verification);
+            };
+            after = last.seq;
+            for entry in batch {
+                if entry.prev_hash != verification.last_hash || entry.compute_hash() != entry.hash {
+                    verification.ok = false;
+                    verification.first_invalid_seq = Some(entry.seq);
+                    return Ok(verification);
+                }
+                verification.entries += 1;
+                verification.last_hash = entry.hash;
+            }
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! 接口密钥认证：配置中只保存密钥的 SHA-256 摘要，每个密钥带有权限范围。
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tiny_http::Request;
+
+use crate::error::ApiError;
+use crate::masking::Masking;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// 查询和统计
+    Read,
+    /// 创建、修改和删除用户
+    Write,
+    /// 配置、健康检查等管理接口；拥有全部权限
+    Admin,
+}
+
+impl Scope {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Scope::Read => "read",
+            Scope::Write => "write",
+            Scope::Admin => "admin",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKey {
+    /// 日志中使用的标识，不是密钥本身
+    pub id: String,
+    /// 密钥的 SHA-256 摘要（十六进制），可用 `userdata-server --hash-api-key` 生成
+    pub sha256: String,
+    pub scopes: Vec<Scope>,
+    /// 限制可查询和可见字段的角色，未设置时可使用全部字段
+    #[serde(default)]
+    pub role: Option<String>,
+    /// 覆盖角色中的脱敏方式
+    #[serde(default)]
+    pub masking: Option<Masking>,
+}
+
+impl ApiKey {
+    pub fn allows(&self, scope: Scope) -> bool {
+        self.scopes.contains(&scope) || self.scopes.contains(&Scope::Admin)
+    }
+}
+
+/// 计算密钥的 SHA-256 十六进制摘要。
+pub fn hash_key(key: &str) -> String {
+    Sha256::digest(key.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 按 `Authorization: Bearer <key>` 认证请求，返回匹配的密钥。
+///
+/// 未配置任何密钥时拒绝所有请求，避免设备上的其他进程无需认证即可读取个人信息。
+pub(crate) fn authorize<'a>(keys: &'a [ApiKey], request: &Request, required: Scope) -> Result<&'a ApiKey, ApiError> {
+    if keys.is_empty() {
+        return Err(ApiError::new(
+            403,
+            "api_keys_required",
+            "No API keys configured; add api_keys to the server config to enable the API",
+        ));
+    }
+
+    let hash = request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv("Authorization"))
+        .and_then(|h| h.value.as_str().strip_prefix("Bearer "))
+        .map(|token| hash_key(token.trim()));
+    let key = hash
+        .and_then(|hash| keys.iter().find(|key| key.sha256.eq_ignore_ascii_case(&hash)))
+        .ok_or_else(|| {
+            ApiError::new(401, "unauthorized", "Missing or invalid API key").with_header("WWW-Authenticate: Bearer")
+        })?;
+
+    if !key.allows(required) {
+        return Err(ApiError::new(
+            403,
+            "insufficient_scope",
+            format!("API key '{}' lacks the {} scope", key.id, required.name()),
+        ));
+    }
+    Ok(key)
+}
@@ -0,0 +1,288 @@
+//! 在工作站上运行与 Android 应用内相同的用户数据服务。
+//!
+//! 配置优先级：命令行参数 > 环境变量 > 配置文件 > 默认值。
+
+use clap::Parser;
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use log::{error, info, warn};
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+use userdata_rust::audit::AuditLog;
+use userdata_rust::config::Secret;
+use userdata_rust::{auth, migrations, Lifecycle, Server, ServerConfig, ServerHandle};
+
+#[derive(Parser)]
+#[command(name = "userdata-server", version, about = "Run the user data HTTP server")]
+struct Cli {
+    /// JSON or TOML config file
+    #[arg(short, long, env = "USERDATA_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// SQLite database file
+    #[arg(long, env = "USERDATA_DB_PATH")]
+    db_path: Option<String>,
+
+    /// SQLCipher key for an encrypted database (requires the sqlcipher feature)
+    #[arg(long, env = "USERDATA_DB_KEY", hide_env_values = true)]
+    db_key: Option<String>,
+
+    /// Address to bind
+    #[arg(long, env = "USERDATA_HOST")]
+    host: Option<String>,
+
+    /// Port to listen on
+    #[arg(short, long, env = "USERDATA_PORT")]
+    port: Option<u16>,
+
+    /// Number of request worker threads
+    #[arg(long, env = "USERDATA_WORKERS")]
+    workers: Option<usize>,
+
+    /// Pending request queue length
+    #[arg(long, env = "USERDATA_QUEUE_CAPACITY")]
+    queue_capacity: Option<usize>,
+
+    /// Number of read-only database connections
+    #[arg(long, env = "USERDATA_DB_POOL_SIZE")]
+    db_pool_size: Option<usize>,
+
+    /// Maximum number of records returned per query
+    #[arg(long, env = "USERDATA_MAX_PAGE_SIZE")]
+    max_page_size: Option<usize>,
+
+    /// Milliseconds to wait for in-flight requests when stopping
+    #[arg(long, env = "USERDATA_SHUTDOWN_TIMEOUT_MS")]
+    shutdown_timeout_ms: Option<u64>,
+
+    /// Create indexes for unindexed lookup columns at startup
+    #[arg(long, env = "USERDATA_CREATE_MISSING_INDEXES")]
+    create_missing_indexes: bool,
+
+    /// Audit log database (default: <db-path>.audit)
+    #[arg(long, env = "USERDATA_AUDIT_DB_PATH")]
+    audit_db_path: Option<String>,
+
+    /// Secret for the HMAC digests of looked-up values in the audit log
+    #[arg(long, env = "USERDATA_AUDIT_SECRET", hide_env_values = true)]
+    audit_secret: Option<String>,
+
+    /// Create or migrate the database schema, then exit
+    #[arg(long)]
+    migrate_only: bool,
+
+    /// Write an encrypted copy of the plaintext database to OUTPUT using --db-key, then exit
+    #[arg(long, value_name = "OUTPUT")]
+    encrypt_db: Option<PathBuf>,
+
+    /// Print every audit log entry as a JSON line, then exit
+    #[arg(long)]
+    export_audit: bool,
+
+    /// Check the audit log hash chain, then exit non-zero if it is broken
+    #[arg(long)]
+    verify_audit: bool,
+
+    /// Print the SHA-256 digest to put in an `api_keys` entry, then exit
+    #[arg(long, value_name = "KEY")]
+    hash_api_key: Option<String>,
+}
+
+impl Cli {
+    fn into_config(self) -> Result<ServerConfig, userdata_rust::config::ConfigError> {
+        let mut config = match &self.config {
+            Some(path) => ServerConfig::from_file(path)?,
+            None => ServerConfig::default(),
+        };
+        if let Some(db_path) = self.db_path {
+            config.db_path = db_path;
+        }
+        if let Some(db_key) = self.db_key {
+            config.db_key = Some(Secret::new(db_key));
+        }
+        if let Some(host) = self.host {
+            config.host = host;
+        }
+        if let Some(port) = self.port {
+            config.port = port;
+        }
+        if let Some(workers) = self.workers {
+            config.workers = workers;
+        }
+        if let Some(queue_capacity) = self.queue_capacity {
+            config.queue_capacity = queue_capacity;
+        }
+        if let Some(db_pool_size) = self.db_pool_size {
+            config.db_pool_size = db_pool_size;
+        }
+        if let Some(max_page_size) = self.max_page_size {
+            config.max_page_size = max_page_size;
+        }
+        if let Some(shutdown_timeout_ms) = self.shutdown_timeout_ms {
+            config.shutdown_timeout_ms = shutdown_timeout_ms;
+        }
+        if let Some(audit_db_path) = self.audit_db_path {
+            config.audit_db_path = Some(audit_db_path);
+        }
+        if let Some(audit_secret) = self.audit_secret {
+            config.audit_secret = Some(Secret::new(audit_secret));
+        }
+        if self.create_missing_indexes {
+            config.create_missing_indexes = true;
+        }
+        Ok(config)
+    }
+}
+
+fn main() -> ExitCode {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    let cli = Cli::parse();
+    if let Some(key) = &cli.hash_api_key {
+        println!("{}", auth::hash_key(key));
+        return ExitCode::SUCCESS;
+    }
+    let migrate_only = cli.migrate_only;
+    let (export_audit, verify_audit) = (cli.export_audit, cli.verify_audit);
+    let encrypt_db = cli.encrypt_db.clone();
+    // 各子命令与启动服务使用同一套配置校验
+    let config = match cli.into_config().and_then(|c| c.validate().map(|()| c)) {
+        Ok(c) => c,
+        Err(e) => {
+            error!("{}", e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if migrate_only {
+        // 与启动服务时一致：自定义表结构属于外部数据库，不做迁移
+        if !config.schema.is_default() {
+            error!("Custom schema mapping configured for table '{}', refusing to migrate", config.schema.table);
+            return ExitCode::FAILURE;
+        }
+        return match migrations::run(&config.db_path, config.db_key.as_ref().map(Secret::expose)) {
+            Ok(report) => {
+                info!("{}: schema version {} -> {}", config.db_path, report.from_version, report.to_version);
+                ExitCode::SUCCESS
+            }
+            Err(e) => {
+                error!("Migration failed: {}", e);
+                ExitCode::FAILURE
+            }
+        };
+    }
+
+    if let Some(output) = encrypt_db {
+        return encrypt_command(&config, &output);
+    }
+
+    if export_audit || verify_audit {
+        return audit_command(&config, export_audit);
+    }
+
+    // 在启动前安装处理函数，启动期间收到的信号会在服务启动后立即生效
+    let (signal_tx, signal_rx) = crossbeam_channel::bounded(1);
+    if let Err(e) = ctrlc::set_handler(move || {
+        let _ = signal_tx.try_send(());
+    }) {
+        warn!("Cannot install signal handler, in-flight requests will not be drained on exit: {}", e);
+    }
+
+    match Server::start(config) {
+        Ok(handle) => match run_until_signal(handle, signal_rx) {
+            Lifecycle::Failed(reason) => {
+                error!("{}", reason);
+                ExitCode::FAILURE
+            }
+            _ => ExitCode::SUCCESS,
+        },
+        Err(e) => {
+            error!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+// 收到 SIGINT/SIGTERM 时正常停止服务（处理中的请求在期限内处理完）；服务自行退出时直接返回其最终状态
+fn run_until_signal(mut handle: ServerHandle, signals: Receiver<()>) -> Lifecycle {
+    loop {
+        match signals.recv_timeout(Duration::from_millis(200)) {
+            Ok(()) => {
+                info!("Signal received, stopping server");
+                return handle.stop();
+            }
+            Err(RecvTimeoutError::Timeout) if handle.is_running() => {}
+            Err(_) => return handle.join(),
+        }
+    }
+}
+
+// 导出时先输出全部记录，再校验；校验失败返回非零
+fn audit_command(config: &ServerConfig, export: bool) -> ExitCode {
+    let path = AuditLog::path_for(config);
+    if !std::path::Path::new(&path).exists() {
+        error!("Audit log {} does not exist", path);
+        return ExitCode::FAILURE;
+    }
+    let result = AuditLog::open_for(config).and_then(|audit| {
+        if export {
+            let mut after = 0;
+            loop {
+                let entries = audit.entries(after, 1000)?;
+                let Some(last) = entries.last() else { break };
+                after = last.seq;
+                for entry in &entries {
+                    println!("{}", serde_json::to_string(entry).unwrap_or_default());
+                }
+            }
+        }
+        audit.verify()
+    });
+    match result {
+        Ok(verification) if verification.ok => {
+            info!("{}: {} entries, chain intact, last hash {}", path, verification.entries, verification.last_hash);
+            ExitCode::SUCCESS
+        }
+        Ok(verification) => {
+            error!(
+                "{}: hash chain broken at entry {}",
+                path,
+                verification.first_invalid_seq.unwrap_or_default()
+            );
+            ExitCode::FAILURE
+        }
+        Err(e) => {
+            error!("Cannot read audit log {}: {}", path, e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(feature = "sqlcipher")]
+fn encrypt_command(config: &ServerConfig, output: &std::path::Path) -> ExitCode {
+    let Some(key) = &config.db_key else {
+        error!("--encrypt-db needs the key to encrypt with (--db-key or USERDATA_DB_KEY)");
+        return ExitCode::FAILURE;
+    };
+    if output.exists() {
+        error!("{} already exists", output.display());
+        return ExitCode::FAILURE;
+    }
+    match userdata_rust::db::encrypt_database(&config.db_path, &output.to_string_lossy(), key.expose()) {
+        Ok(()) => {
+            info!("Encrypted {} into {}", config.db_path, output.display());
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            error!("Encryption failed: {}", e);
+            let _ = std::fs::remove_file(output);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(not(feature = "sqlcipher"))]
+fn encrypt_command(_config: &ServerConfig, _output: &std::path::Path) -> ExitCode {
+    error!("This build has no SQLCipher support; rebuild with --features sqlcipher");
+    ExitCode::FAILURE
+}
@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use crate::access::Role;
+use crate::auth::ApiKey;
+use crate::schema::SchemaMapping;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub db_path: String,
+    /// SQLCipher 数据库密钥（需要 `sqlcipher` 特性）；不会通过 `/config` 返回
+    #[serde(skip_serializing)]
+    pub db_key: Option<Secret>,
+    pub host: String,
+    pub port: u16,
+    /// 请求处理线程数
+    pub workers: usize,
+    /// 等待处理的请求队列长度，队列满时返回 503
+    pub queue_capacity: usize,
+    /// 只读数据库连接数
+    pub db_pool_size: usize,
+    /// 请求体大小上限（字节），超过时返回 413
+    pub max_body_bytes: usize,
+    /// 单次查询最多返回的记录数
+    pub max_page_size: usize,
+    /// 停止时等待处理中请求完成的最长时间（毫秒）
+    pub shutdown_timeout_ms: u64,
+    /// 接口密钥；为空时所有请求都返回 403 `api_keys_required`。不会通过 `/config` 返回
+    #[serde(skip_serializing)]
+    pub api_keys: Vec<ApiKey>,
+    /// 按名称定义的角色，由密钥的 `role` 引用
+    pub roles: BTreeMap<String, Role>,
+    /// 表名和列名映射
+    pub schema: SchemaMapping,
+    /// 启动时为缺少索引的查询列建索引（需要数据库可写）
+    pub create_missing_indexes: bool,
+    /// 审计日志数据库路径，默认为 `<db_path>.audit`
+    pub audit_db_path: Option<String>,
+    /// 审计日志中查询值摘要（HMAC-SHA256）的密钥，不能与日志放在一起；未设置时只记录字段名
+    #[serde(skip_serializing)]
+    pub audit_secret: Option<Secret>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            db_path: "/data/data/com.example.userdata_rust/files/user_data.db".to_string(),
+            db_key: None,
+            host: "127.0.0.1".to_string(),
+            port: 8080,
+            workers: 4,
+            queue_capacity: 32,
+            db_pool_size: 4,
+            max_body_bytes: 64 * 1024,
+            max_page_size: 100,
+            shutdown_timeout_ms: 5000,
+            api_keys: Vec::new(),
+            roles: BTreeMap::new(),
+            schema: SchemaMapping::default(),
+            create_missing_indexes: false,
+            audit_db_path: None,
+            audit_secret: None,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// 从 JSON 或 TOML 文件读取配置，按扩展名区分格式，缺省字段使用默认值。
+    pub fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let content = std::fs::read_to_string(path).map_err(ConfigError::Io)?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => toml::from_str(&content).map_err(|e| ConfigError::Parse(e.to_string())),
+            _ => serde_json::from_str(&content).map_err(|e| ConfigError::Parse(e.to_string())),
+        }
+    }
+
+    /// 检查数据库密钥、角色引用和角色中的字段名。
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.db_key.is_some() && !cfg!(feature = "sqlcipher") {
+            return Err(ConfigError::Invalid("db_key requires a build with the sqlcipher feature".to_string()));
+        }
+        for (name, role) in &self.roles {
+            let unknown = role.unknown_fields();
+            if !unknown.is_empty() {
+                return Err(ConfigError::Invalid(format!("Role '{}' names unknown fields: {}", name, unknown.join(", "))));
+            }
+        }
+        for key in &self.api_keys {
+            if let Some(role) = key.role.as_ref().filter(|role| !self.roles.contains_key(*role)) {
+                return Err(ConfigError::Invalid(format!("API key '{}' refers to unknown role '{}'", key.id, role)));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 只在内存中保存的密钥，Debug 输出时隐藏内容。
+#[derive(Clone, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(value: impl Into<String>) -> Self {
+        Secret(value.into())
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Secret(..)")
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    Parse(String),
+    Invalid(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "Cannot read config file: {}", e),
+            ConfigError::Parse(e) => write!(f, "Invalid config file: {}", e),
+            ConfigError::Invalid(e) => write!(f, "Invalid config: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
@@ -0,0 +1,349 @@
+use crossbeam_channel::{self, Receiver, Sender};
+use log::{info, warn};
+use rusqlite::{params, params_from_iter, types::Value, Connection, OpenFlags, OptionalExtension, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::schema::SchemaMapping;
+
+/// 可用于查询的字段
+pub const LOOKUP_FIELDS: [&str; 3] = ["phone", "qq", "email"];
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UserInfo {
+    /// 记录的 rowid，新建记录时忽略
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub qq: Option<String>,
+}
+
+impl UserInfo {
+    /// 校验字段格式，至少需要一个非空字段。
+    pub fn validate(&self) -> Result<(), String> {
+        if self.email.is_none() && self.phone.is_none() && self.qq.is_none() {
+            return Err("At least one of email, phone, qq is required".to_string());
+        }
+        if let Some(email) = &self.email {
+            let valid = email.len() <= 254
+                && email.split_once('@').is_some_and(|(local, domain)| {
+                    !local.is_empty() && domain.contains('.') && !domain.starts_with('.') && !domain.ends_with('.')
+                })
+                && !email.chars().any(char::is_whitespace);
+            if !valid {
+                return Err(format!("Invalid email '{}'", email));
+            }
+        }
+        if let Some(phone) = &self.phone {
+            let digits = phone.strip_prefix('+').unwrap_or(phone);
+            if !(5..=20).contains(&digits.len()) || !digits.chars().all(|c| c.is_ascii_digit()) {
+                return Err(format!("Invalid phone '{}'", phone));
+            }
+        }
+        if let Some(qq) = &self.qq {
+            if !(5..=12).contains(&qq.len()) || !qq.chars().all(|c| c.is_ascii_digit()) || qq.starts_with('0') {
+                return Err(format!("Invalid qq '{}'", qq));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 部分更新：外层 `None` 表示不修改，`Some(None)` 表示清空该字段。
+#[derive(Debug, Default)]
+pub struct UserPatch {
+    pub email: Option<Option<String>>,
+    pub phone: Option<Option<String>>,
+    pub qq: Option<Option<String>>,
+}
+
+impl UserPatch {
+    pub fn apply(self, user: &mut UserInfo) {
+        if let Some(email) = self.email {
+            user.email = email;
+        }
+        if let Some(phone) = self.phone {
+            user.phone = phone;
+        }
+        if let Some(qq) = self.qq {
+            user.qq = qq;
+        }
+    }
+}
+
+/// 数据库连接：多个只读连接供查询并行使用，另有一个写连接串行处理修改。
+pub struct DbPool {
+    sender: Sender<Connection>,
+    receiver: Receiver<Connection>,
+    writer: Option<Mutex<Connection>>,
+}
+
+impl DbPool {
+    pub fn open(path: &str, size: usize, key: Option<&str>) -> rusqlite::Result<Self> {
+        let writer = open_writer(path, key).map(Mutex::new);
+
+        let size = size.max(1);
+        let (sender, receiver) = crossbeam_channel::bounded(size);
+        for _ in 0..size {
+            let conn = open_connection(
+                path,
+                OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+                key,
+            )?;
+            let _ = sender.send(conn);
+        }
+        info!("Opened {} read-only connections to {}", size, path);
+        Ok(Self { sender, receiver, writer })
+    }
+
+    /// 写连接，数据库只读时为 `None`。
+    pub fn writer(&self) -> Option<MutexGuard<'_, Connection>> {
+        self.writer.as_ref().map(|writer| writer.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    /// 取出一个空闲连接，全部被占用时阻塞等待。
+    pub fn get(&self) -> PooledConnection<'_> {
+        let conn = self.receiver.recv().expect("connection pool holds its own sender");
+        PooledConnection { pool: self, conn: Some(conn) }
+    }
+}
+
+pub struct PooledConnection<'a> {
+    pool: &'a DbPool,
+    conn: Option<Connection>,
+}
+
+impl Deref for PooledConnection<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().unwrap()
+    }
+}
+
+impl Drop for PooledConnection<'_> {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            let _ = self.pool.sender.send(conn);
+        }
+    }
+}
+
+/// 打开数据库连接；提供 `key` 时先用它解密，密钥错误会在这里返回错误。
+pub fn open_connection(path: &str, flags: OpenFlags, key: Option<&str>) -> rusqlite::Result<Connection> {
+    let conn = Connection::open_with_flags(path, flags)?;
+    if let Some(key) = key {
+        conn.pragma_update(None, "key", key)?;
+        // SQLCipher 在第一次读取时才校验密钥
+        conn.query_row("SELECT count(*) FROM sqlite_master", [], |_| Ok(()))?;
+    }
+    Ok(conn)
+}
+
+/// 把明文数据库导出为用 `key` 加密的新文件，保留表结构版本。
+#[cfg(feature = "sqlcipher")]
+pub fn encrypt_database(plain_path: &str, encrypted_path: &str, key: &str) -> rusqlite::Result<()> {
+    let conn = Connection::open_with_flags(plain_path, OpenFlags::SQLITE_OPEN_READ_WRITE)?;
+    // ATTACH 沿用明文连接的打开标志，不会新建文件，先用密钥建好空库
+    open_connection(encrypted_path, OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE, Some(key))?;
+    conn.execute("ATTACH DATABASE ?1 AS encrypted KEY ?2", params![encrypted_path, key])?;
+    conn.query_row("SELECT sqlcipher_export('encrypted')", [], |_| Ok(()))?;
+    let version: u32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    conn.pragma_update(Some(rusqlite::DatabaseName::Attached("encrypted")), "user_version", version)?;
+    conn.execute("DETACH DATABASE encrypted", [])?;
+    Ok(())
+}
+
+// 打开写连接并尽量切换到 WAL 模式，这样读连接不会被写操作阻塞；文件不可写时返回 None
+fn open_writer(path: &str, key: Option<&str>) -> Option<Connection> {
+    let conn = match open_connection(path, OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_NO_MUTEX, key) {
+        Ok(conn) => conn,
+        Err(e) => {
+            warn!("Database is read-only: {}", e);
+            return None;
+        }
+    };
+    match conn.query_row("PRAGMA journal_mode = WAL", [], |row| row.get::<_, String>(0)) {
+        Ok(mode) if mode.eq_ignore_ascii_case("wal") => {}
+        Ok(mode) => warn!("Database stays in {} journal mode", mode),
+        Err(e) => {
+            warn!("Database is read-only: {}", e);
+            return None;
+        }
+    }
+    let _ = conn.busy_timeout(Duration::from_secs(5));
+    Some(conn)
+}
+
+pub fn count_records(conn: &Connection, schema: &SchemaMapping) -> rusqlite::Result<i64> {
+    let sql = format!("SELECT COUNT(*) FROM {}", schema.table_sql());
+    conn.query_row(&sql, [], |row| row.get::<_, i64>(0))
+}
+
+/// 取出参数中已识别且非空的查询字段，顺序与 `LOOKUP_FIELDS` 一致。
+pub fn lookup_filters(params: &HashMap<String, String>) -> Vec<(&'static str, &str)> {
+    LOOKUP_FIELDS
+        .iter()
+        .filter_map(|&field| {
+            params
+                .get(field)
+                .filter(|value| !value.is_empty())
+                .map(|value| (field, value.as_str()))
+        })
+        .collect()
+}
+
+/// 一页查询结果，`next_cursor` 为本页最后一条记录的 rowid，没有更多结果时为 `None`。
+#[derive(Debug)]
+pub struct Page {
+    pub items: Vec<UserInfo>,
+    pub next_cursor: Option<i64>,
+}
+
+/// 按所有给定字段同时匹配（AND）查询用户，从 `after` 之后按 rowid 顺序最多取 `limit` 条。
+pub fn query_database(
+    conn: &Connection,
+    schema: &SchemaMapping,
+    filters: &[(&str, &str)],
+    after: Option<i64>,
+    limit: usize,
+) -> rusqlite::Result<Page> {
+    if filters.is_empty() {
+        return Ok(Page { items: Vec::new(), next_cursor: None });
+    }
+
+    let clause = filters
+        .iter()
+        .enumerate()
+        .map(|(i, (field, _))| format!("{} = ?{}", schema.column_sql(field), i + 1))
+        .collect::<Vec<_>>()
+        .join(" AND ");
+    let sql = format!(
+        "{} WHERE {} AND rowid > ?{} ORDER BY rowid LIMIT ?{}",
+        schema.select_sql(),
+        clause,
+        filters.len() + 1,
+        filters.len() + 2
+    );
+
+    let mut values = filters.iter().map(|(_, value)| Value::from(value.to_string())).collect::<Vec<_>>();
+    values.push(Value::from(after.unwrap_or(i64::MIN)));
+    // 多取一条用来判断是否还有下一页
+    values.push(Value::from(limit as i64 + 1));
+
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(params_from_iter(values), |row| {
+        Ok((row.get::<_, i64>(0)?, user_from_row(row)?))
+    })?;
+    let mut rows = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let next_cursor = if rows.len() > limit {
+        rows.truncate(limit);
+        rows.last().map(|(rowid, _)| *rowid)
+    } else {
+        None
+    };
+    Ok(Page {
+        items: rows.into_iter().map(|(_, user)| user).collect(),
+        next_cursor,
+    })
+}
+
+fn user_from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<UserInfo> {
+    Ok(UserInfo {
+        id: Some(row.get(0)?),
+        email: row.get(1)?,
+        phone: row.get(2)?,
+        qq: row.get(3)?,
+    })
+}
+
+pub fn get_user(conn: &Connection, schema: &SchemaMapping, id: i64) -> rusqlite::Result<Option<UserInfo>> {
+    let sql = format!("{} WHERE rowid = ?1", schema.select_sql());
+    conn.query_row(&sql, [id], user_from_row).optional()
+}
+
+#[derive(Debug)]
+pub enum WriteError {
+    NotFound,
+    Invalid(String),
+    Database(rusqlite::Error),
+}
+
+impl From<rusqlite::Error> for WriteError {
+    fn from(e: rusqlite::Error) -> Self {
+        WriteError::Database(e)
+    }
+}
+
+/// 在调用方的事务中插入记录；提交由调用方负责，以便先写入审计记录。
+pub fn insert_user(tx: &Transaction<'_>, schema: &SchemaMapping, user: &UserInfo) -> Result<UserInfo, WriteError> {
+    user.validate().map_err(WriteError::Invalid)?;
+
+    let sql = format!(
+        "INSERT INTO {} ({}, {}, {}) VALUES (?1, ?2, ?3)",
+        schema.table_sql(),
+        schema.column_sql("email"),
+        schema.column_sql("phone"),
+        schema.column_sql("qq")
+    );
+    tx.execute(&sql, params![user.email, user.phone, user.qq])?;
+    get_user(tx, schema, tx.last_insert_rowid())?.ok_or(WriteError::NotFound)
+}
+
+/// 在调用方的事务中读取记录、应用修改、校验并写回，不提交。
+pub fn update_user(tx: &Transaction<'_>, schema: &SchemaMapping, id: i64, patch: UserPatch) -> Result<UserInfo, WriteError> {
+    let mut user = get_user(tx, schema, id)?.ok_or(WriteError::NotFound)?;
+    patch.apply(&mut user);
+    user.validate().map_err(WriteError::Invalid)?;
+
+    let sql = format!(
+        "UPDATE {} SET {} = ?1, {} = ?2, {} = ?3 WHERE rowid = ?4",
+        schema.table_sql(),
+        schema.column_sql("email"),
+        schema.column_sql("phone"),
+        schema.column_sql("qq")
+    );
+    tx.execute(&sql, params![user.email, user.phone, user.qq, id])?;
+    Ok(user)
+}
+
+/// 在调用方的事务中删除记录，不提交。
+pub fn delete_user(tx: &Transaction<'_>, schema: &SchemaMapping, id: i64) -> Result<(), WriteError> {
+    let sql = format!("DELETE FROM {} WHERE rowid = ?1", schema.table_sql());
+    if tx.execute(&sql, [id])? == 0 {
+        return Err(WriteError::NotFound);
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+pub struct DatabaseStats {
+    pub total_records: i64,
+    pub unique_phones: i64,
+    pub unique_qqs: i64,
+    pub unique_emails: i64,
+}
+
+pub fn get_database_stats(conn: &Connection, schema: &SchemaMapping) -> rusqlite::Result<DatabaseStats> {
+    let distinct = |field: &str| {
+        let column = schema.column_sql(field);
+        let sql = format!(
+            "SELECT COUNT(DISTINCT {}) FROM {} WHERE {} IS NOT NULL",
+            column,
+            schema.table_sql(),
+            column
+        );
+        conn.query_row(&sql, [], |row| row.get::<_, i64>(0))
+    };
+    Ok(DatabaseStats {
+        total_records: count_records(conn, schema)?,
+        unique_phones: distinct("phone")?,
+        unique_qqs: distinct("qq")?,
+        unique_emails: distinct("email")?,
+    })
+}
@@ -0,0 +1,93 @@
+use log::error;
+use serde_json::json;
+use std::io::Cursor;
+use tiny_http::{Header, Response};
+
+use crate::db::WriteError;
+
+/// 接口错误，统一以 `{"error": {"code": ..., "message": ...}}` 返回。
+#[derive(Debug)]
+pub(crate) struct ApiError {
+    pub status: u16,
+    pub code: &'static str,
+    pub message: String,
+    pub headers: Vec<Header>,
+}
+
+impl ApiError {
+    pub fn new(status: u16, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code,
+            message: message.into(),
+            headers: Vec::new(),
+        }
+    }
+
+    pub fn with_header(mut self, header: &str) -> Self {
+        self.headers.push(header.parse::<Header>().unwrap());
+        self
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(400, "bad_request", message)
+    }
+
+    pub fn not_found(path: &str) -> Self {
+        Self::new(404, "not_found", format!("No route for {}", path))
+    }
+
+    pub fn method_not_allowed(allowed: &str) -> Self {
+        Self::new(405, "method_not_allowed", format!("Allowed methods: {}", allowed))
+            .with_header(&format!("Allow: {}", allowed))
+    }
+
+    pub fn payload_too_large(limit: usize) -> Self {
+        Self::new(413, "payload_too_large", format!("Request body exceeds {} bytes", limit))
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(500, "internal_error", message)
+    }
+
+    pub fn service_unavailable() -> Self {
+        Self::new(503, "service_unavailable", "Server is busy, retry later")
+            .with_header("Retry-After: 1")
+    }
+
+    pub fn into_response(self) -> Response<Cursor<Vec<u8>>> {
+        if self.status >= 500 {
+            error!("{} {}: {}", self.status, self.code, self.message);
+        }
+        let body = json!({ "error": { "code": self.code, "message": self.message } });
+        let mut response = Response::from_string(body.to_string())
+            .with_status_code(self.status)
+            .with_header("Content-Type: application/json".parse::<Header>().unwrap());
+        for header in self.headers {
+            response.add_header(header);
+        }
+        response
+    }
+}
+
+impl From<rusqlite::Error> for ApiError {
+    fn from(e: rusqlite::Error) -> Self {
+        Self::new(500, "database_error", e.to_string())
+    }
+}
+
+impl From<WriteError> for ApiError {
+    fn from(e: WriteError) -> Self {
+        match e {
+            WriteError::NotFound => Self::new(404, "user_not_found", "User does not exist"),
+            WriteError::Invalid(message) => Self::new(400, "invalid_user", message),
+            WriteError::Database(e) => e.into(),
+        }
+    }
+}
+
+impl From<serde_json::Error> for ApiError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::internal(format!("Serialization failed: {}", e))
+    }
+}
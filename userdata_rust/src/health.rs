@@ -0,0 +1,95 @@
+//! 数据库健康检查：完整性、表结构、索引和存储参数。
+
+use rusqlite::{Connection, OpenFlags};
+use serde::Serialize;
+
+use crate::db;
+use crate::migrations;
+use crate::schema::{self, IndexStatus, SchemaMapping};
+
+#[derive(Debug, Serialize)]
+pub struct HealthReport {
+    /// 完整性检查和表结构校验都通过
+    pub ok: bool,
+    pub db_path: String,
+    pub sqlite_version: String,
+    pub file_size_bytes: Option<u64>,
+    pub page_size: i64,
+    pub page_count: i64,
+    pub journal_mode: String,
+    pub schema_version: u32,
+    pub record_count: Option<i64>,
+    pub integrity: IntegrityCheck,
+    pub schema: SchemaCheck,
+    pub indexes: Vec<IndexStatus>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IntegrityCheck {
+    /// `quick_check` 或 `integrity_check`
+    pub mode: &'static str,
+    pub ok: bool,
+    pub messages: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SchemaCheck {
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+/// 以只读方式打开数据库并生成报告；`full` 为 true 时执行较慢的 `integrity_check`。
+pub fn check_path(db_path: &str, key: Option<&str>, mapping: &SchemaMapping, full: bool) -> rusqlite::Result<HealthReport> {
+    let conn = db::open_connection(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY, key)?;
+    check(&conn, db_path, mapping, full)
+}
+
+pub fn check(conn: &Connection, db_path: &str, mapping: &SchemaMapping, full: bool) -> rusqlite::Result<HealthReport> {
+    let pragma_i64 = |name: &str| conn.query_row(&format!("PRAGMA {}", name), [], |row| row.get::<_, i64>(0));
+
+    let integrity = integrity_check(conn, full)?;
+    let schema = match schema::validate(conn, mapping) {
+        Ok(()) => SchemaCheck { ok: true, error: None },
+        Err(e) => SchemaCheck { ok: false, error: Some(e.to_string()) },
+    };
+    let record_count = if schema.ok {
+        crate::db::count_records(conn, mapping).ok()
+    } else {
+        None
+    };
+    let indexes = if schema.ok {
+        schema::check_indexes(conn, mapping)?
+    } else {
+        Vec::new()
+    };
+
+    Ok(HealthReport {
+        ok: integrity.ok && schema.ok,
+        db_path: db_path.to_string(),
+        sqlite_version: rusqlite::version().to_string(),
+        file_size_bytes: std::fs::metadata(db_path).map(|m| m.len()).ok(),
+        page_size: pragma_i64("page_size")?,
+        page_count: pragma_i64("page_count")?,
+        journal_mode: conn.query_row("PRAGMA journal_mode", [], |row| row.get(0))?,
+        schema_version: migrations::user_version(conn)?,
+        record_count,
+        integrity,
+        schema,
+        indexes,
+    })
+}
+
+// 最多收集 20 条问题，避免损坏严重时报告过大
+fn integrity_check(conn: &Connection, full: bool) -> rusqlite::Result<IntegrityCheck> {
+    let mode = if full { "integrity_check" } else { "quick_check" };
+    let mut stmt = conn.prepare(&format!("PRAGMA {}(20)", mode))?;
+    let messages = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    let ok = messages.len() == 1 && messages[0] == "ok";
+    Ok(IntegrityCheck {
+        mode,
+        ok,
+        messages: if ok { Vec::new() } else { messages },
+    })
+}
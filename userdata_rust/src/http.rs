@@ -0,0 +1,376 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use rusqlite::{Connection, Transaction};
+use std::collections::BTreeMap;
+use std::io::Cursor;
+use std::sync::MutexGuard;
+use tiny_http::{Header, Method, Request, Response};
+
+use crate::db::{
+    delete_user, get_database_stats, get_user, insert_user, lookup_filters, query_database, update_user,
+    DatabaseStats, UserInfo, UserPatch, WriteError, LOOKUP_FIELDS,
+};
+use crate::access::Access;
+use crate::audit::AuditEvent;
+use crate::auth::{authorize, Scope};
+use crate::error::ApiError;
+use crate::health;
+use crate::schema::check_indexes;
+use crate::params::{decode_path_segment, read_params, split_url, Params};
+use crate::server::AppState;
+
+type HttpResponse = Response<Cursor<Vec<u8>>>;
+
+pub(crate) fn handle_request(mut request: Request, state: &AppState) {
+    state.monitor.request_started();
+    let method = request.method().clone();
+    let path = split_url(request.url()).0.to_string();
+    let key = authorize(&state.config.api_keys, &request, required_scope(&method, &path));
+    let mut audit = AuditEvent {
+        key_id: key.as_ref().ok().map(|key| key.id.clone()),
+        method: method.to_string(),
+        route: audited_route(&method, &path),
+        ..AuditEvent::default()
+    };
+    let mut response = key
+        .map(|key| Access::resolve(key, &state.config.roles))
+        .and_then(|access| route(&mut request, state, &access, &mut audit))
+        .unwrap_or_else(|e| error_response(state, e));
+
+    // 审计记录写不进去时不返回数据；写操作已在提交前记录
+    if !audit.recorded {
+        if let Err(e) = state.audit.append(&audit, response.status_code().0) {
+            response = error_response(state, audit_failed(e));
+        }
+    }
+
+    // 在写出响应前计数，客户端收到响应时计数已更新
+    state.monitor.request_finished(response.status_code().0);
+    let _ = request.respond(response);
+}
+
+fn audit_failed(e: rusqlite::Error) -> ApiError {
+    ApiError::new(500, "audit_failed", format!("Failed to write audit log: {}", e))
+}
+
+// 两个数据库无法在同一事务中提交：先写审计记录再提交写操作，审计失败时写操作随事务回滚。
+// 审计之后提交失败时，请求结束时会再记一条 500，表明前一条记录的写入没有生效
+fn commit_audited(state: &AppState, audit: &mut AuditEvent, tx: Transaction<'_>, status: u16) -> Result<(), ApiError> {
+    if let Err(e) = state.audit.append(audit, status) {
+        audit.result_count = None;
+        return Err(audit_failed(e));
+    }
+    if let Err(e) = tx.commit() {
+        audit.result_count = None;
+        return Err(e.into());
+    }
+    audit.recorded = true;
+    Ok(())
+}
+
+fn error_response(state: &AppState, e: ApiError) -> HttpResponse {
+    if e.status >= 500 {
+        state.monitor.record_error(format!("{}: {}", e.code, e.message));
+    }
+    e.into_response()
+}
+
+pub(crate) fn respond_unavailable(request: Request, state: &AppState) {
+    state.monitor.request_rejected();
+    let _ = request.respond(ApiError::service_unavailable().into_response());
+}
+
+// 认证在路由之前完成：管理接口需要 admin，用户写操作需要 write，其余需要 read
+fn required_scope(method: &Method, path: &str) -> Scope {
+    let segments = path.split('/').filter(|s| !s.is_empty()).collect::<Vec<_>>();
+    match segments.as_slice() {
+        ["config"] | ["api", "v1", "config"] | ["api", "v1", "health", "db"] | ["api", "v1", "audit", ..] => Scope::Admin,
+        ["api", "v1", "users"] | ["api", "v1", "users", _] if *method != Method::Get => Scope::Write,
+        _ => Scope::Read,
+    }
+}
+
+// 查询和写入用户数据的请求需要审计，返回不含具体值的路由模板
+fn audited_route(method: &Method, path: &str) -> Option<&'static str> {
+    let segments = path.split('/').filter(|s| !s.is_empty()).collect::<Vec<_>>();
+    match segments.as_slice() {
+        ["query"] => Some("/query"),
+        ["api", "v1", "query"] => Some("/api/v1/query"),
+        ["api", "v1", "users"] if *method == Method::Get || *method == Method::Post => Some("/api/v1/users"),
+        ["api", "v1", "users", _] => Some("/api/v1/users/{id}"),
+        ["api", "v1", "users", _, _] => Some("/api/v1/users/{field}/{value}"),
+        _ => None,
+    }
+}
+
+// `/api/v1/...` 是正式接口；`/`、`/config`、`/query`、`/stats` 保留旧的响应格式以兼容已有调用方，
+// 其中 `/query` 只返回第一页结果
+fn route(request: &mut Request, state: &AppState, access: &Access, audit: &mut AuditEvent) -> Result<HttpResponse, ApiError> {
+    let path = split_url(request.url()).0.to_string();
+    let segments = path.split('/').filter(|s| !s.is_empty()).collect::<Vec<_>>();
+    let method = request.method().clone();
+
+    match segments.as_slice() {
+        [] => {
+            allow(&method, &[Method::Get])?;
+            Ok(Response::from_string("User Data Server Running"))
+        }
+        ["config"] | ["api", "v1", "config"] => {
+            allow(&method, &[Method::Get])?;
+            json_response(&state.config)
+        }
+        ["query"] => {
+            allow(&method, &[Method::Get, Method::Post])?;
+            let params = read_params(request, state.config.max_body_bytes)?;
+            json_response(&lookup(state, access, &params, audit)?.items)
+        }
+        ["stats"] => {
+            allow(&method, &[Method::Post])?;
+            let stats = get_database_stats(&state.db.get(), &state.config.schema)?;
+            Ok(Response::from_string(stats_html(&stats))
+                .with_header("Content-Type: text/html".parse::<Header>().unwrap()))
+        }
+        ["api", "v1", "status"] => {
+            allow(&method, &[Method::Get])?;
+            let indexes = check_indexes(&state.db.get(), &state.config.schema)?;
+            let mut status = serde_json::to_value(state.monitor.status())?;
+            status["indexes"] = serde_json::to_value(indexes)?;
+            json_response(&status)
+        }
+        ["api", "v1", "query"] => {
+            allow(&method, &[Method::Get, Method::Post])?;
+            let params = read_params(request, state.config.max_body_bytes)?;
+            json_response(&lookup(state, access, &params, audit)?)
+        }
+        ["api", "v1", "health", "db"] => {
+            allow(&method, &[Method::Get])?;
+            let params = read_params(request, state.config.max_body_bytes)?;
+            let full = params.get("full").is_some_and(|v| v == "1" || v == "true");
+            let report = health::check(&state.db.get(), &state.config.db_path, &state.config.schema, full)?;
+            let status = if report.ok { 200 } else { 503 };
+            Ok(json_response(&report)?.with_status_code(status))
+        }
+        ["api", "v1", "stats"] => {
+            allow(&method, &[Method::Get])?;
+            json_response(&get_database_stats(&state.db.get(), &state.config.schema)?)
+        }
+        // GET /api/v1/users?phone=... 与 GET /api/v1/users/phone/{value} 等价
+        ["api", "v1", "users"] => {
+            allow(&method, &[Method::Get, Method::Post])?;
+            if method == Method::Post {
+                let params = read_params(request, state.config.max_body_bytes)?;
+                audit.fields = written_fields(&params);
+                access.check_writes(&audit.fields.iter().map(|(name, _)| *name).collect::<Vec<_>>())?;
+                let mut conn = writer(state)?;
+                let tx = conn.transaction()?;
+                let created = insert_user(&tx, &state.config.schema, &user_from_params(&params))?;
+                audit.result_count = Some(1);
+                commit_audited(state, audit, tx, 201)?;
+                let location = format!("Location: /api/v1/users/{}", created.id.unwrap_or_default());
+                return Ok(json_response(&access.project(&created))?
+                    .with_status_code(201)
+                    .with_header(location.parse::<Header>().unwrap()));
+            }
+            let params = read_params(request, state.config.max_body_bytes)?;
+            lookup_response(state, access, &params, audit)
+        }
+        ["api", "v1", "users", id] => {
+            allow(&method, &[Method::Get, Method::Put, Method::Patch, Method::Delete])?;
+            audit.fields.push(("id", id.to_string()));
+            let id = id.parse::<i64>().map_err(|_| ApiError::not_found(&path))?;
+            if method == Method::Get {
+                let user = get_user(&state.db.get(), &state.config.schema, id)?.ok_or(WriteError::NotFound)?;
+                audit.result_count = Some(1);
+                return json_response(&access.project(&user));
+            }
+
+            let params = read_params(request, state.config.max_body_bytes)?;
+            let written = written_fields(&params);
+            // PUT 会清空未提供的字段，DELETE 删除整条记录，都视为写入全部字段
+            if method == Method::Patch {
+                access.check_writes(&written.iter().map(|(name, _)| *name).collect::<Vec<_>>())?;
+            } else {
+                access.check_writes(&LOOKUP_FIELDS)?;
+            }
+            audit.fields.extend(written);
+            // 写入成功后才记录影响的记录数，失败的请求不计
+            let mut conn = writer(state)?;
+            let tx = conn.transaction()?;
+            match method {
+                Method::Put | Method::Patch => {
+                    let patch = if method == Method::Put { replace_from_params(&params) } else { patch_from_params(&params) };
+                    let user = update_user(&tx, &state.config.schema, id, patch)?;
+                    audit.result_count = Some(1);
+                    commit_audited(state, audit, tx, 200)?;
+                    json_response(&access.project(&user))
+                }
+                _ => {
+                    delete_user(&tx, &state.config.schema, id)?;
+                    audit.result_count = Some(1);
+                    commit_audited(state, audit, tx, 204)?;
+                    Ok(Response::from_data(Vec::new()).with_status_code(204))
+                }
+            }
+        }
+        ["api", "v1", "users", field, value] => {
+            allow(&method, &[Method::Get])?;
+            if !LOOKUP_FIELDS.contains(field) {
+                return Err(ApiError::not_found(&path));
+            }
+            let params = Params::from([(field.to_string(), decode_path_segment(value)?)]);
+            lookup_response(state, access, &params, audit)
+        }
+        ["api", "v1", "audit"] => {
+            allow(&method, &[Method::Get])?;
+            let params = read_params(request, state.config.max_body_bytes)?;
+            let (after, limit) = page_params(&params, state.config.max_page_size)?;
+            let entries = state.audit.entries(after.unwrap_or(0), limit)?;
+            let next_cursor = (entries.len() == limit).then(|| entries[limit - 1].seq.to_string());
+            json_response(&json!({ "items": entries, "next_cursor": next_cursor }))
+        }
+        ["api", "v1", "audit", "verify"] => {
+            allow(&method, &[Method::Get])?;
+            let verification = state.audit.verify()?;
+            let status = if verification.ok { 200 } else { 409 };
+            Ok(json_response(&verification)?.with_status_code(status))
+        }
+        _ => Err(ApiError::not_found(&path)),
+    }
+}
+
+#[derive(Serialize)]
+struct LookupResult<'a> {
+    /// 多个查询字段之间的组合方式，目前固定为 "all"（AND）
+    #[serde(rename = "match")]
+    match_mode: &'static str,
+    filters: BTreeMap<&'static str, &'a str>,
+    /// 按调用方角色裁剪后的记录
+    items: Vec<Value>,
+    /// 传给下一次请求的 `cursor` 参数
+    next_cursor: Option<String>,
+    /// 本页之后还有更多结果
+    truncated: bool,
+}
+
+fn lookup<'a>(state: &AppState, access: &Access, params: &'a Params, audit: &mut AuditEvent) -> Result<LookupResult<'a>, ApiError> {
+    let filters = lookup_filters(params);
+    audit.fields = filters.iter().map(|(field, value)| (*field, value.to_string())).collect();
+    if filters.is_empty() {
+        return Err(ApiError::new(
+            400,
+            "missing_filter",
+            format!("Supply at least one of: {}", LOOKUP_FIELDS.join(", ")),
+        ));
+    }
+
+    access.check_filters(&filters)?;
+
+    let (after, limit) = page_params(params, state.config.max_page_size)?;
+    let page = query_database(&state.db.get(), &state.config.schema, &filters, after, limit)?;
+    audit.result_count = Some(page.items.len());
+    Ok(LookupResult {
+        match_mode: "all",
+        filters: filters.into_iter().collect(),
+        items: page.items.iter().map(|user| access.project(user)).collect(),
+        truncated: page.next_cursor.is_some(),
+        next_cursor: page.next_cursor.map(|cursor| cursor.to_string()),
+    })
+}
+
+/// 解析 `cursor` 和 `limit`，`limit` 缺省或超过上限时按上限取。
+fn page_params(params: &Params, max_page_size: usize) -> Result<(Option<i64>, usize), ApiError> {
+    let after = match params.get("cursor").filter(|c| !c.is_empty()) {
+        Some(cursor) => Some(
+            cursor
+                .parse::<i64>()
+                .map_err(|_| ApiError::new(400, "invalid_cursor", format!("Invalid cursor '{}'", cursor)))?,
+        ),
+        None => None,
+    };
+    let limit = match params.get("limit").filter(|l| !l.is_empty()) {
+        Some(limit) => match limit.parse::<usize>() {
+            Ok(n) if n > 0 => n.min(max_page_size),
+            _ => return Err(ApiError::new(400, "invalid_limit", format!("Invalid limit '{}'", limit))),
+        },
+        None => max_page_size,
+    };
+    Ok((after, limit.max(1)))
+}
+
+fn lookup_response(state: &AppState, access: &Access, params: &Params, audit: &mut AuditEvent) -> Result<HttpResponse, ApiError> {
+    Ok(json_response(&lookup(state, access, params, audit)?)?
+        .with_header("Cache-Control: private, max-age=60".parse::<Header>().unwrap()))
+}
+
+fn writer(state: &AppState) -> Result<MutexGuard<'_, Connection>, ApiError> {
+    state
+        .db
+        .writer()
+        .ok_or_else(|| ApiError::new(403, "read_only_database", "Database is opened read-only"))
+}
+
+// 空字符串表示该字段为空
+fn field(params: &Params, name: &str) -> Option<String> {
+    params.get(name).filter(|value| !value.is_empty()).cloned()
+}
+
+fn user_from_params(params: &Params) -> UserInfo {
+    UserInfo {
+        id: None,
+        email: field(params, "email"),
+        phone: field(params, "phone"),
+        qq: field(params, "qq"),
+    }
+}
+
+// 写请求中提供的用户字段，空字符串表示清空
+fn written_fields(params: &Params) -> Vec<(&'static str, String)> {
+    LOOKUP_FIELDS
+        .into_iter()
+        .filter_map(|name| params.get(name).map(|value| (name, value.clone())))
+        .collect()
+}
+
+/// PUT：未提供的字段被清空。
+fn replace_from_params(params: &Params) -> UserPatch {
+    UserPatch {
+        email: Some(field(params, "email")),
+        phone: Some(field(params, "phone")),
+        qq: Some(field(params, "qq")),
+    }
+}
+
+/// PATCH：只修改提供了的字段。
+fn patch_from_params(params: &Params) -> UserPatch {
+    UserPatch {
+        email: params.contains_key("email").then(|| field(params, "email")),
+        phone: params.contains_key("phone").then(|| field(params, "phone")),
+        qq: params.contains_key("qq").then(|| field(params, "qq")),
+    }
+}
+
+fn allow(method: &Method, allowed: &[Method]) -> Result<(), ApiError> {
+    if allowed.contains(method) {
+        return Ok(());
+    }
+    let allowed = allowed.iter().map(|m| m.as_str()).collect::<Vec<_>>().join(", ");
+    Err(ApiError::method_not_allowed(&allowed))
+}
+
+fn json_response<T: Serialize>(value: &T) -> Result<HttpResponse, ApiError> {
+    let body = serde_json::to_string(value)?;
+    Ok(Response::from_string(body)
+        .with_header("Content-Type: application/json".parse::<Header>().unwrap()))
+}
+
+fn stats_html(stats: &DatabaseStats) -> String {
+    format!(r#"
+        <h2>Database Statistics</h2>
+        <ul>
+            <li>Total Records: {}</li>
+            <li>Unique Phones: {}</li>
+            <li>Unique QQs: {}</li>
+            <li>Unique Emails: {}</li>
+        </ul>
+        "#, stats.total_records, stats.unique_phones, stats.unique_qqs, stats.unique_emails)
+}
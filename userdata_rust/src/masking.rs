@@ -0,0 +1,62 @@
+//! 返回结果中个人信息的脱敏方式。
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Masking {
+    /// 原样返回
+    #[default]
+    None,
+    /// 保留部分字符，例如 `138****5678`、`a***@example.com`、`10***89`
+    Partial,
+    /// 整个值替换为 `****`
+    Full,
+}
+
+impl Masking {
+    /// 按字段（`email`、`phone`、`qq`）处理单个值。
+    pub fn apply(self, field: &str, value: &str) -> String {
+        match self {
+            Masking::None => value.to_string(),
+            Masking::Full => "****".to_string(),
+            Masking::Partial => match field {
+                "email" => mask_email(value),
+                "phone" => mask_phone(value),
+                _ => mask_qq(value),
+            },
+        }
+    }
+}
+
+/// 保留前 3 位和后 4 位，例如 `13812345678` → `138****5678`。
+pub fn mask_phone(phone: &str) -> String {
+    keep_ends(phone, 3, 4)
+}
+
+/// 保留用户名首字符和域名，例如 `alice@example.com` → `a***@example.com`。
+pub fn mask_email(email: &str) -> String {
+    match email.rsplit_once('@') {
+        Some((local, domain)) => {
+            let first = local.chars().next().map(String::from).unwrap_or_default();
+            format!("{}***@{}", first, domain)
+        }
+        None => keep_ends(email, 1, 0),
+    }
+}
+
+/// 保留前 2 位和后 2 位，例如 `123456789` → `12*****89`。
+pub fn mask_qq(qq: &str) -> String {
+    keep_ends(qq, 2, 2)
+}
+
+// 值太短、保留部分会暴露全部内容时整体隐藏
+fn keep_ends(value: &str, head: usize, tail: usize) -> String {
+    let chars = value.chars().collect::<Vec<_>>();
+    let (head, tail) = if chars.len() > head + tail { (head, tail) } else { (0, 0) };
+    let hidden = chars.len().saturating_sub(head + tail).max(1);
+    let mut masked = chars[..head].iter().collect::<String>();
+    masked.push_str(&"*".repeat(hidden));
+    masked.extend(&chars[chars.len().saturating_sub(tail)..]);
+    masked
+}
@@ -0,0 +1,214 @@
+//! users 数据库的版本化迁移，当前版本记录在 `PRAGMA user_version` 中。
+
+use log::{info, warn};
+use rusqlite::{Connection, OpenFlags, Transaction};
+use std::fmt;
+use std::path::Path;
+
+use crate::db;
+use crate::schema::quote;
+
+type Migration = fn(&Transaction<'_>) -> Result<(), MigrationError>;
+
+// 按顺序执行，第 N 个迁移完成后 user_version 为 N；已发布的迁移不要修改，只在末尾追加
+const MIGRATIONS: &[Migration] = &[create_users, add_primary_key_and_timestamps, add_lookup_indexes];
+
+pub const LATEST_VERSION: u32 = MIGRATIONS.len() as u32;
+
+#[derive(Debug)]
+pub enum MigrationError {
+    Database(rusqlite::Error),
+    /// 现有表结构无法自动迁移
+    UnsupportedSchema(String),
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationError::Database(e) => write!(f, "{}", e),
+            MigrationError::UnsupportedSchema(message) => write!(f, "Unsupported schema: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+impl From<rusqlite::Error> for MigrationError {
+    fn from(e: rusqlite::Error) -> Self {
+        MigrationError::Database(e)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MigrationReport {
+    pub from_version: u32,
+    pub to_version: u32,
+}
+
+/// 打开（必要时创建）数据库并执行所有未应用的迁移。
+///
+/// 已存在的数据库只能以只读方式打开时跳过迁移，仍按原有结构提供查询。
+pub fn run(db_path: &str, key: Option<&str>) -> Result<MigrationReport, MigrationError> {
+    let flags = OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_CREATE;
+    let mut conn = match db::open_connection(db_path, flags, key) {
+        Ok(conn) => conn,
+        Err(e) if Path::new(db_path).exists() => {
+            warn!("Cannot open {} for writing, skipping migrations: {}", db_path, e);
+            let conn = db::open_connection(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY, key)?;
+            let version = user_version(&conn)?;
+            return Ok(MigrationReport { from_version: version, to_version: version });
+        }
+        Err(e) => return Err(e.into()),
+    };
+    migrate(&mut conn)
+}
+
+pub fn migrate(conn: &mut Connection) -> Result<MigrationReport, MigrationError> {
+    let from_version = user_version(conn)?;
+    if from_version > LATEST_VERSION {
+        warn!("Database schema version {} is newer than supported version {}", from_version, LATEST_VERSION);
+    }
+
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(from_version as usize) {
+        let version = index as u32 + 1;
+        let tx = conn.transaction()?;
+        migration(&tx)?;
+        tx.pragma_update(None, "user_version", version)?;
+        tx.commit()?;
+        info!("Applied database migration {}", version);
+    }
+
+    Ok(MigrationReport {
+        from_version,
+        to_version: user_version(conn)?,
+    })
+}
+
+pub fn user_version(conn: &Connection) -> rusqlite::Result<u32> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+fn create_users(tx: &Transaction<'_>) -> Result<(), MigrationError> {
+    tx.execute_batch("CREATE TABLE IF NOT EXISTS users (email TEXT, phone TEXT, qq TEXT);")?;
+    Ok(())
+}
+
+// SQLite 不能给已有表加主键，只能重建；id 沿用原 rowid，原表中的其他列和已有的时间戳原样保留
+fn add_primary_key_and_timestamps(tx: &Transaction<'_>) -> Result<(), MigrationError> {
+    let mut stmt = tx.prepare("SELECT name, type, pk FROM pragma_table_info('users')")?;
+    let columns = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)? > 0)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    // 已有的非主键 id 列无法与新的主键共存，交由人工处理以免丢数据
+    if columns.iter().any(|(name, _, pk)| name.eq_ignore_ascii_case("id") && !pk) {
+        return Err(MigrationError::UnsupportedSchema(
+            "users.id exists but is not the primary key; migrate it manually".to_string(),
+        ));
+    }
+    let dropped = rebuild_would_drop(tx)?;
+    if !dropped.is_empty() {
+        return Err(MigrationError::UnsupportedSchema(format!(
+            "rebuilding users would drop {}; migrate it manually",
+            dropped.join(", ")
+        )));
+    }
+
+    let is_timestamp = |name: &str| name.eq_ignore_ascii_case("created_at") || name.eq_ignore_ascii_case("updated_at");
+    let definitions = columns
+        .iter()
+        .filter(|(name, _, _)| !name.eq_ignore_ascii_case("id") && !is_timestamp(name))
+        .map(|(name, ty, _)| format!("{} {},", quote(name), ty).replace(" ,", ","))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let copied = columns
+        .iter()
+        .filter(|(name, _, _)| !name.eq_ignore_ascii_case("id"))
+        .map(|(name, _, _)| format!(", {}", quote(name)))
+        .collect::<String>();
+
+    tx.execute_batch(&format!(
+        "CREATE TABLE users_new (
+             id INTEGER PRIMARY KEY,
+             {definitions}
+             created_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now')),
+             updated_at TEXT DEFAULT (strftime('%Y-%m-%dT%H:%M:%SZ', 'now'))
+         );
+         INSERT INTO users_new (id{copied}) SELECT rowid{copied} FROM users;
+         DROP TABLE users;
+         ALTER TABLE users_new RENAME TO users;
+         CREATE TRIGGER users_touch_updated_at AFTER UPDATE ON users
+         BEGIN
+             UPDATE users SET updated_at = strftime('%Y-%m-%dT%H:%M:%SZ', 'now') WHERE id = NEW.id;
+         END;"
+    ))?;
+    Ok(())
+}
+
+// 重建只保留列名和类型，列约束、表约束、外键、生成列以及依附在表上的索引和触发器都会丢失
+fn rebuild_would_drop(tx: &Transaction<'_>) -> rusqlite::Result<Vec<String>> {
+    let mut dropped = Vec::new();
+
+    // pragma_table_info 不列出生成列，用 table_xinfo 才能看到
+    let mut stmt = tx.prepare("SELECT name, \"notnull\", dflt_value IS NOT NULL, pk, hidden FROM pragma_table_xinfo('users')")?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let name = row.get::<_, String>(0)?;
+        if row.get::<_, i64>(4)? != 0 {
+            dropped.push(format!("generated column {}", name));
+            continue;
+        }
+        if row.get::<_, bool>(1)? {
+            dropped.push(format!("{} NOT NULL", name));
+        }
+        if row.get::<_, bool>(2)? {
+            dropped.push(format!("{} DEFAULT", name));
+        }
+        if row.get::<_, i64>(3)? > 0 && !name.eq_ignore_ascii_case("id") {
+            dropped.push(format!("{} PRIMARY KEY", name));
+        }
+    }
+
+    let mut stmt = tx.prepare(
+        "SELECT DISTINCT \"from\", \"table\" FROM pragma_foreign_key_list('users') ORDER BY id",
+    )?;
+    for foreign_key in stmt.query_map([], |row| Ok(format!("{} REFERENCES {}", row.get::<_, String>(0)?, row.get::<_, String>(1)?)))? {
+        dropped.push(foreign_key?);
+    }
+
+    // UNIQUE 约束对应的自动索引没有 sql，单独从 pragma_index_list 中找
+    let mut stmt = tx.prepare("SELECT name FROM pragma_index_list('users') WHERE origin = 'u'")?;
+    for name in stmt.query_map([], |row| row.get::<_, String>(0))? {
+        dropped.push(format!("UNIQUE constraint {}", name?));
+    }
+
+    let mut stmt = tx.prepare(
+        "SELECT type, name FROM sqlite_master
+         WHERE tbl_name = 'users' AND type IN ('index', 'trigger') AND sql IS NOT NULL ORDER BY type, name",
+    )?;
+    for object in stmt.query_map([], |row| Ok(format!("{} {}", row.get::<_, String>(0)?, row.get::<_, String>(1)?)))? {
+        dropped.push(object?);
+    }
+
+    // CHECK 和 COLLATE 只能从建表语句中看出；按单词匹配，列名里碰巧含有这些词时宁可拒绝
+    let sql: String = tx.query_row("SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'users'", [], |row| row.get(0))?;
+    let words = sql
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .map(str::to_ascii_uppercase)
+        .collect::<Vec<_>>();
+    for clause in ["CHECK", "COLLATE"] {
+        if words.iter().any(|word| word == clause) {
+            dropped.push(format!("{} clause", clause));
+        }
+    }
+    Ok(dropped)
+}
+
+fn add_lookup_indexes(tx: &Transaction<'_>) -> Result<(), MigrationError> {
+    tx.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_users_phone ON users (phone);
+         CREATE INDEX IF NOT EXISTS idx_users_qq ON users (qq);
+         CREATE INDEX IF NOT EXISTS idx_users_email ON users (email);",
+    )?;
+    Ok(())
+}
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use std::io::Read;
+use tiny_http::{Method, Request};
+
+use crate::error::ApiError;
+
+pub(crate) type Params = HashMap<String, String>;
+
+/// 把请求路径和查询字符串分开。
+pub(crate) fn split_url(url: &str) -> (&str, &str) {
+    url.split_once('?').unwrap_or((url, ""))
+}
+
+/// 读取请求参数：查询字符串总是参与解析，POST/PUT/PATCH 请求再按 Content-Type 解析请求体，同名时请求体优先。
+pub(crate) fn read_params(request: &mut Request, max_body_bytes: usize) -> Result<Params, ApiError> {
+    let (_, query) = split_url(request.url());
+    let mut params = parse_urlencoded(query)?;
+
+    if matches!(request.method(), Method::Post | Method::Put | Method::Patch) {
+        let content_type = request
+            .headers()
+            .iter()
+            .find(|h| h.field.equiv("Content-Type"))
+            .map(|h| h.value.as_str().to_string());
+
+        let content = read_body(request, max_body_bytes)?;
+
+        let body = match content_type.as_deref().map(media_type) {
+            None | Some("application/x-www-form-urlencoded") => parse_urlencoded(&content)?,
+            Some("application/json") => parse_json(&content)?,
+            Some(other) => return Err(ApiError::bad_request(format!("Unsupported Content-Type: {}", other))),
+        };
+        params.extend(body);
+    }
+
+    Ok(params)
+}
+
+/// 读取请求体，超过上限时返回 413。
+fn read_body(request: &mut Request, max_body_bytes: usize) -> Result<String, ApiError> {
+    if request.body_length().is_some_and(|len| len > max_body_bytes) {
+        return Err(ApiError::payload_too_large(max_body_bytes));
+    }
+
+    let mut content = Vec::new();
+    request
+        .as_reader()
+        .take(max_body_bytes as u64 + 1)
+        .read_to_end(&mut content)
+        .map_err(|e| ApiError::bad_request(format!("Cannot read request body: {}", e)))?;
+    if content.len() > max_body_bytes {
+        return Err(ApiError::payload_too_large(max_body_bytes));
+    }
+    String::from_utf8(content).map_err(|_| ApiError::bad_request("Request body is not valid UTF-8"))
+}
+
+fn media_type(content_type: &str) -> &str {
+    content_type.split(';').next().unwrap_or("").trim()
+}
+
+pub(crate) fn parse_urlencoded(content: &str) -> Result<Params, ApiError> {
+    let mut params = HashMap::new();
+    for pair in content.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let key = percent_decode(key)?;
+        if key.is_empty() {
+            continue;
+        }
+        params.insert(key, percent_decode(value)?);
+    }
+    Ok(params)
+}
+
+/// JSON 请求体必须是对象，字符串和数字值被接受，null 视为未提供。
+fn parse_json(content: &str) -> Result<Params, ApiError> {
+    let value: serde_json::Value = serde_json::from_str(content)
+        .map_err(|e| ApiError::bad_request(format!("Invalid JSON body: {}", e)))?;
+    let object = value
+        .as_object()
+        .ok_or_else(|| ApiError::bad_request("JSON body must be an object"))?;
+
+    let mut params = HashMap::new();
+    for (key, value) in object {
+        match value {
+            serde_json::Value::String(s) => {
+                params.insert(key.clone(), s.clone());
+            }
+            serde_json::Value::Number(n) => {
+                params.insert(key.clone(), n.to_string());
+            }
+            serde_json::Value::Null => {}
+            _ => return Err(ApiError::bad_request(format!("Field '{}' must be a string or number", key))),
+        }
+    }
+    Ok(params)
+}
+
+/// 按表单规则解码，`+` 表示空格。
+pub(crate) fn percent_decode(input: &str) -> Result<String, ApiError> {
+    decode(input, true)
+}
+
+/// 解码路径中的一段；路径里的 `+` 是字面字符，例如 `+8613800000001`。
+pub(crate) fn decode_path_segment(input: &str) -> Result<String, ApiError> {
+    decode(input, false)
+}
+
+fn decode(input: &str, plus_as_space: bool) -> Result<String, ApiError> {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' if plus_as_space => decoded.push(b' '),
+            b'%' => {
+                let hex = bytes
+                    .get(i + 1..i + 3)
+                    .and_then(|h| std::str::from_utf8(h).ok())
+                    .and_then(|h| u8::from_str_radix(h, 16).ok())
+                    .ok_or_else(|| ApiError::bad_request(format!("Invalid percent-encoding in '{}'", input)))?;
+                decoded.push(hex);
+                i += 2;
+            }
+            b => decoded.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8(decoded).map_err(|_| ApiError::bad_request(format!("Invalid UTF-8 in '{}'", input)))
+}
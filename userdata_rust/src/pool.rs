@@ -0,0 +1,67 @@
+use crossbeam_channel::{self, Receiver, RecvTimeoutError, Sender, TrySendError};
+use log::{info, warn};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+use tiny_http::Request;
+
+use crate::http::{handle_request, respond_unavailable};
+use crate::server::AppState;
+
+/// 固定数量的请求处理线程，共享一个有界队列。
+pub(crate) struct WorkerPool {
+    sender: Sender<Request>,
+    state: Arc<AppState>,
+    workers: Vec<JoinHandle<()>>,
+    // 每个工作线程持有一个发送端，全部退出后接收端断开
+    exited: Receiver<()>,
+}
+
+impl WorkerPool {
+    pub fn new(size: usize, queue_capacity: usize, state: Arc<AppState>) -> Self {
+        let (sender, receiver) = crossbeam_channel::bounded::<Request>(queue_capacity);
+        let (exit_tx, exited) = crossbeam_channel::bounded::<()>(0);
+        let workers = (0..size.max(1))
+            .map(|_| {
+                let receiver = receiver.clone();
+                let state = Arc::clone(&state);
+                let exit_tx = exit_tx.clone();
+                thread::spawn(move || {
+                    let _exit_tx = exit_tx;
+                    for request in receiver.iter() {
+                        handle_request(request, &state);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        info!("Worker pool started with {} threads", workers.len());
+        Self { sender, state, workers, exited }
+    }
+
+    /// 把请求放入队列；队列已满时直接返回 503。
+    pub fn dispatch(&self, request: Request) {
+        match self.sender.try_send(request) {
+            Ok(()) => {}
+            Err(TrySendError::Full(request)) | Err(TrySendError::Disconnected(request)) => {
+                warn!("Worker pool saturated, rejecting request");
+                respond_unavailable(request, &self.state);
+            }
+        }
+    }
+
+    /// 关闭队列并等待已接收的请求处理完，最多等到 `deadline`。
+    ///
+    /// 超时返回 false，仍在处理请求的线程会被放弃，不再等待。
+    pub fn shutdown(self, deadline: Instant) -> bool {
+        drop(self.sender);
+        match self.exited.recv_deadline(deadline) {
+            Err(RecvTimeoutError::Disconnected) => {
+                for worker in self.workers {
+                    let _ = worker.join();
+                }
+                true
+            }
+            Ok(()) | Err(RecvTimeoutError::Timeout) => false,
+        }
+    }
+}
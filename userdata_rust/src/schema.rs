@@ -0,0 +1,174 @@
+//! 表名、列名映射以及启动时的表结构校验。
+
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// 接口字段到数据库表和列的映射，默认对应迁移创建的 `users` 表。
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct SchemaMapping {
+    pub table: String,
+    pub email: String,
+    pub phone: String,
+    pub qq: String,
+}
+
+impl Default for SchemaMapping {
+    fn default() -> Self {
+        Self {
+            table: "users".to_string(),
+            email: "email".to_string(),
+            phone: "phone".to_string(),
+            qq: "qq".to_string(),
+        }
+    }
+}
+
+impl SchemaMapping {
+    /// 是否为迁移管理的默认表结构；自定义映射的表结构由外部维护，不执行迁移。
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// 接口字段名对应的列名。
+    pub fn column(&self, field: &str) -> Option<&str> {
+        match field {
+            "email" => Some(&self.email),
+            "phone" => Some(&self.phone),
+            "qq" => Some(&self.qq),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn table_sql(&self) -> String {
+        quote(&self.table)
+    }
+
+    /// 已加引号的列名，用于拼接 SQL。
+    pub(crate) fn column_sql(&self, field: &str) -> String {
+        quote(self.column(field).unwrap_or(field))
+    }
+
+    /// `SELECT rowid, email, phone, qq FROM users` 对应的 SQL。
+    pub(crate) fn select_sql(&self) -> String {
+        format!(
+            "SELECT rowid, {}, {}, {} FROM {}",
+            quote(&self.email),
+            quote(&self.phone),
+            quote(&self.qq),
+            self.table_sql()
+        )
+    }
+}
+
+#[derive(Debug)]
+pub enum SchemaError {
+    Database(rusqlite::Error),
+    MissingTable(String),
+    MissingColumns { table: String, columns: Vec<String> },
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaError::Database(e) => write!(f, "{}", e),
+            SchemaError::MissingTable(table) => write!(f, "Table '{}' does not exist", table),
+            SchemaError::MissingColumns { table, columns } => {
+                write!(f, "Table '{}' is missing columns: {}", table, columns.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+impl From<rusqlite::Error> for SchemaError {
+    fn from(e: rusqlite::Error) -> Self {
+        SchemaError::Database(e)
+    }
+}
+
+/// 通过 `PRAGMA table_info` 确认映射的表和列都存在。
+pub fn validate(conn: &Connection, mapping: &SchemaMapping) -> Result<(), SchemaError> {
+    let columns = table_columns(conn, &mapping.table)?;
+    if columns.is_empty() {
+        return Err(SchemaError::MissingTable(mapping.table.clone()));
+    }
+
+    let missing = [&mapping.email, &mapping.phone, &mapping.qq]
+        .into_iter()
+        .filter(|column| !columns.iter().any(|c| c.eq_ignore_ascii_case(column)))
+        .cloned()
+        .collect::<Vec<_>>();
+    if !missing.is_empty() {
+        return Err(SchemaError::MissingColumns {
+            table: mapping.table.clone(),
+            columns: missing,
+        });
+    }
+    Ok(())
+}
+
+/// 查询字段的索引情况。
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexStatus {
+    pub field: &'static str,
+    pub column: String,
+    /// 以该列开头的非部分索引名，没有时为 `None`
+    pub index: Option<String>,
+}
+
+impl IndexStatus {
+    pub fn is_indexed(&self) -> bool {
+        self.index.is_some()
+    }
+}
+
+/// 通过 `PRAGMA index_list`/`index_info` 检查每个查询列是否有可用于等值查找的索引。
+pub fn check_indexes(conn: &Connection, mapping: &SchemaMapping) -> rusqlite::Result<Vec<IndexStatus>> {
+    let mut stmt = conn.prepare(
+        "SELECT il.name FROM pragma_index_list(?1) AS il, pragma_index_info(il.name) AS ii
+         WHERE il.partial = 0 AND ii.seqno = 0 AND ii.name = ?2 COLLATE NOCASE
+         LIMIT 1",
+    )?;
+    ["phone", "qq", "email"]
+        .into_iter()
+        .map(|field| {
+            let column = mapping.column(field).unwrap_or(field).to_string();
+            let index = stmt
+                .query_row([&mapping.table, &column], |row| row.get(0))
+                .optional()?;
+            Ok(IndexStatus { field, column, index })
+        })
+        .collect()
+}
+
+/// 为缺少索引的查询列建索引，返回新建的索引名。
+pub fn create_missing_indexes(conn: &Connection, mapping: &SchemaMapping) -> rusqlite::Result<Vec<String>> {
+    let mut created = Vec::new();
+    for status in check_indexes(conn, mapping)?.into_iter().filter(|s| !s.is_indexed()) {
+        let name = format!("idx_{}_{}", mapping.table, status.column)
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect::<String>();
+        conn.execute_batch(&format!(
+            "CREATE INDEX IF NOT EXISTS {} ON {} ({})",
+            quote(&name),
+            mapping.table_sql(),
+            quote(&status.column)
+        ))?;
+        created.push(name);
+    }
+    Ok(created)
+}
+
+pub fn table_columns(conn: &Connection, table: &str) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT name FROM pragma_table_info(?1)")?;
+    let columns = stmt.query_map([table], |row| row.get(0))?;
+    columns.collect()
+}
+
+pub(crate) fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
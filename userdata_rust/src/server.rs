@@ -0,0 +1,293 @@
+use log::{info, warn};
+use std::fmt;
+use std::net::SocketAddr;
+use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use crossbeam_channel::{self, Sender, Receiver};
+
+use crate::audit::AuditLog;
+use crate::config::{ConfigError, Secret, ServerConfig};
+use crate::db::DbPool;
+use crate::migrations::{self, MigrationError};
+use crate::schema::{self, SchemaError};
+use crate::status::{Lifecycle, ServerMonitor, ServerStatus};
+use crate::pool::WorkerPool;
+
+pub(crate) struct AppState {
+    pub config: ServerConfig,
+    pub db: DbPool,
+    pub monitor: ServerMonitor,
+    pub audit: AuditLog,
+}
+
+pub struct Server;
+
+/// 启动失败的具体原因，在 [`Server::start`] 返回前同步报告。
+#[derive(Debug)]
+pub enum StartError {
+    Config(ConfigError),
+    Migration(MigrationError),
+    Database(rusqlite::Error),
+    Schema(SchemaError),
+    Audit(rusqlite::Error),
+    Bind { addr: String, message: String },
+    /// 服务线程在报告结果前退出
+    Thread,
+}
+
+impl StartError {
+    /// 机器可读的错误码
+    pub fn code(&self) -> &'static str {
+        match self {
+            StartError::Config(_) => "invalid_config",
+            StartError::Migration(_) => "migration_failed",
+            StartError::Database(_) => "database_error",
+            StartError::Schema(_) => "schema_mismatch",
+            StartError::Audit(_) => "audit_unavailable",
+            StartError::Bind { .. } => "bind_failed",
+            StartError::Thread => "thread_failed",
+        }
+    }
+}
+
+impl fmt::Display for StartError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StartError::Config(e) => write!(f, "{}", e),
+            StartError::Migration(e) => write!(f, "Failed to migrate database: {}", e),
+            StartError::Database(e) => write!(f, "Failed to open database: {}", e),
+            StartError::Schema(e) => write!(f, "Database schema does not match configuration: {}", e),
+            StartError::Audit(e) => write!(f, "Failed to open audit log: {}", e),
+            StartError::Bind { addr, message } => write!(f, "Failed to listen on {}: {}", addr, message),
+            StartError::Thread => write!(f, "Server thread exited during startup"),
+        }
+    }
+}
+
+impl std::error::Error for StartError {}
+
+impl Server {
+    /// 在后台线程中启动 HTTP 服务。
+    ///
+    /// 数据库打开、表结构校验和端口绑定都完成后才返回，失败时返回具体原因。
+    pub fn start(config: ServerConfig) -> Result<ServerHandle, StartError> {
+        Self::start_with_monitor(config, ServerMonitor::new())
+    }
+
+    /// 与 `start` 相同，但使用调用方事先创建的监视器，启动完成前即可从中查询到 `Starting` 状态。
+    pub fn start_with_monitor(config: ServerConfig, monitor: ServerMonitor) -> Result<ServerHandle, StartError> {
+        let (shutdown_tx, shutdown_rx) = crossbeam_channel::bounded(1);
+        let (started_tx, started_rx) = crossbeam_channel::bounded(1);
+
+        let thread_config = config.clone();
+        let thread_monitor = monitor.clone();
+        let thread = thread::spawn(move || {
+            info!("Starting server thread...");
+            let (server, state) = match prepare(thread_config, thread_monitor.clone()) {
+                Ok(prepared) => prepared,
+                Err(e) => {
+                    thread_monitor.failed(e.to_string());
+                    let _ = started_tx.send(Err(e));
+                    return;
+                }
+            };
+            // Server::http 总是监听 TCP 地址
+            let local_addr = server.server_addr().to_ip().expect("TCP listen address");
+            thread_monitor.running(local_addr);
+            let _ = started_tx.send(Ok(local_addr));
+            match serve(server, state, shutdown_rx) {
+                Ok(()) => thread_monitor.stopped(),
+                Err(reason) => {
+                    warn!("{}", reason);
+                    thread_monitor.failed(reason);
+                }
+            }
+            info!("Server thread finished.");
+        });
+
+        match started_rx.recv() {
+            Ok(Ok(local_addr)) => Ok(ServerHandle {
+                config: ServerConfig {
+                    port: local_addr.port(),
+                    ..config
+                },
+                local_addr,
+                monitor,
+                shutdown: Some(shutdown_tx),
+                thread: Some(thread),
+            }),
+            Ok(Err(e)) => {
+                let _ = thread.join();
+                Err(e)
+            }
+            Err(_) => {
+                let _ = thread.join();
+                Err(StartError::Thread)
+            }
+        }
+    }
+}
+
+pub struct ServerHandle {
+    config: ServerConfig,
+    local_addr: SocketAddr,
+    monitor: ServerMonitor,
+    shutdown: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ServerHandle {
+    pub fn config(&self) -> &ServerConfig {
+        &self.config
+    }
+
+    /// 实际监听的地址，配置端口为 0 时由系统分配
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.monitor.lifecycle() == Lifecycle::Running
+    }
+
+    pub fn status(&self) -> ServerStatus {
+        self.monitor.status()
+    }
+
+    /// 可在其他线程中查询状态，停止过程中也不会阻塞。
+    pub fn monitor(&self) -> ServerMonitor {
+        self.monitor.clone()
+    }
+
+    /// 发送停止信号并等待服务线程退出，正在处理的请求会先处理完。返回停止后的最终状态。
+    pub fn stop(&mut self) -> Lifecycle {
+        if let Some(tx) = self.shutdown.take() {
+            self.monitor.stopping();
+            let _ = tx.send(());
+        }
+        self.wait();
+        self.monitor.lifecycle()
+    }
+
+    /// 阻塞直到服务线程退出。
+    pub fn join(mut self) -> Lifecycle {
+        self.wait();
+        self.monitor.lifecycle()
+    }
+
+    fn wait(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                self.monitor.failed("Server thread panicked".to_string());
+            }
+        }
+    }
+}
+
+// 缺少索引时查询会全表扫描；配置允许且数据库可写时自动补建
+fn ensure_indexes(db: &DbPool, config: &ServerConfig) {
+    if config.create_missing_indexes {
+        if let Some(writer) = db.writer() {
+            match schema::create_missing_indexes(&writer, &config.schema) {
+                Ok(created) if !created.is_empty() => info!("Created indexes: {}", created.join(", ")),
+                Ok(_) => {}
+                Err(e) => warn!("Failed to create indexes: {}", e),
+            }
+        }
+    }
+
+    match schema::check_indexes(&db.get(), &config.schema) {
+        Ok(statuses) => {
+            for status in statuses.iter().filter(|s| !s.is_indexed()) {
+                warn!("Lookup column {} is not indexed, queries by {} will scan the table", status.column, status.field);
+            }
+        }
+        Err(e) => warn!("Failed to check indexes: {}", e),
+    }
+}
+
+fn prepare(mut config: ServerConfig, monitor: ServerMonitor) -> Result<(tiny_http::Server, AppState), StartError> {
+    config.validate().map_err(StartError::Config)?;
+    let db_key = config.db_key.as_ref().map(Secret::expose);
+
+    if config.schema.is_default() {
+        let report = migrations::run(&config.db_path, db_key).map_err(StartError::Migration)?;
+        info!("Database schema at version {}", report.to_version);
+    } else {
+        info!("Custom schema mapping configured, skipping migrations");
+    }
+
+    let db = DbPool::open(&config.db_path, config.db_pool_size, db_key).map_err(StartError::Database)?;
+    schema::validate(&db.get(), &config.schema).map_err(StartError::Schema)?;
+    ensure_indexes(&db, &config);
+    let audit = AuditLog::open_for(&config).map_err(StartError::Audit)?;
+    if config.audit_secret.is_none() {
+        warn!("No audit_secret configured: the audit log records queried fields but not their values");
+    }
+    if config.api_keys.is_empty() {
+        warn!("No API keys configured: every request will be rejected");
+    }
+
+    let addr = format!("{}:{}", config.host, config.port);
+    let server = tiny_http::Server::http(&addr).map_err(|e| StartError::Bind {
+        addr: addr.clone(),
+        message: e.to_string(),
+    })?;
+    info!("Server started on {}", server.server_addr());
+    // 端口 0 时记录系统实际分配的端口
+    if let Some(local_addr) = server.server_addr().to_ip() {
+        config.port = local_addr.port();
+    }
+
+    Ok((server, AppState { config, db, monitor, audit }))
+}
+
+// 收到停止信号后先关闭监听端口，再在期限内等待处理中的请求；超时返回原因
+fn serve(server: tiny_http::Server, state: AppState, shutdown_rx: Receiver<()>) -> Result<(), String> {
+    let server = Arc::new(server);
+    let state = Arc::new(state);
+
+    // 收到停止信号（或句柄被丢弃）时唤醒阻塞在 recv() 上的主循环
+    let stopping = Arc::new(AtomicBool::new(false));
+    {
+        let server = Arc::clone(&server);
+        let stopping = Arc::clone(&stopping);
+        let monitor = state.monitor.clone();
+        thread::spawn(move || {
+            let _ = shutdown_rx.recv();
+            monitor.stopping();
+            stopping.store(true, Ordering::SeqCst);
+            server.unblock();
+        });
+    }
+
+    let pool = WorkerPool::new(state.config.workers, state.config.queue_capacity, Arc::clone(&state));
+    loop {
+        match server.recv() {
+            Ok(request) => pool.dispatch(request),
+            Err(_) if stopping.load(Ordering::SeqCst) => {
+                info!("Shutdown signal received, stopping server.");
+                break;
+            }
+            Err(e) => {
+                warn!("Failed to accept request: {}", e);
+                state.monitor.record_error(format!("Failed to accept request: {}", e));
+            }
+        }
+    }
+
+    // 停止接受新连接：最后一个引用释放时 tiny_http 关闭监听端口
+    drop(server);
+
+    let timeout = Duration::from_millis(state.config.shutdown_timeout_ms);
+    if !pool.shutdown(Instant::now() + timeout) {
+        let in_flight = state.monitor.status().requests.in_flight;
+        return Err(format!(
+            "Shutdown deadline of {}ms exceeded with {} requests in flight",
+            state.config.shutdown_timeout_ms, in_flight
+        ));
+    }
+    info!("Server loop ended.");
+    Ok(())
+}
@@ -0,0 +1,194 @@
+//! 服务生命周期状态和请求计数。
+
+use serde::Serialize;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// 服务生命周期：Starting → Running → Stopping → Stopped，启动或运行出错时为 Failed。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", content = "reason", rename_all = "snake_case")]
+pub enum Lifecycle {
+    Stopped,
+    Starting,
+    Running,
+    Stopping,
+    Failed(String),
+}
+
+impl Lifecycle {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Lifecycle::Stopped => "stopped",
+            Lifecycle::Starting => "starting",
+            Lifecycle::Running => "running",
+            Lifecycle::Stopping => "stopping",
+            Lifecycle::Failed(_) => "failed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RequestStats {
+    /// 已接收的请求总数，包括被拒绝的
+    pub total: u64,
+    pub in_flight: u64,
+    /// 4xx 响应数
+    pub client_errors: u64,
+    /// 5xx 响应数，不含因队列已满被拒绝的请求
+    pub server_errors: u64,
+    /// 队列已满时直接返回 503 的请求数
+    pub rejected: u64,
+}
+
+/// 某一时刻的服务状态快照。
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerStatus {
+    #[serde(flatten)]
+    pub lifecycle: Lifecycle,
+    pub address: Option<SocketAddr>,
+    pub uptime_secs: Option<u64>,
+    pub requests: RequestStats,
+    pub last_error: Option<String>,
+}
+
+impl ServerStatus {
+    /// 没有服务实例时的状态；`reason` 为上次启动失败的原因。
+    pub fn idle(reason: Option<String>) -> Self {
+        ServerStatus {
+            lifecycle: reason.clone().map_or(Lifecycle::Stopped, Lifecycle::Failed),
+            address: None,
+            uptime_secs: None,
+            requests: RequestStats::default(),
+            last_error: reason,
+        }
+    }
+}
+
+/// 服务线程、请求处理线程和句柄共享的状态，可克隆后在服务停止期间继续查询。
+#[derive(Clone)]
+pub struct ServerMonitor {
+    inner: Arc<Inner>,
+}
+
+impl Default for ServerMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct Inner {
+    state: Mutex<State>,
+    total: AtomicU64,
+    in_flight: AtomicU64,
+    client_errors: AtomicU64,
+    server_errors: AtomicU64,
+    rejected: AtomicU64,
+}
+
+struct State {
+    lifecycle: Lifecycle,
+    started_at: Option<Instant>,
+    address: Option<SocketAddr>,
+    last_error: Option<String>,
+}
+
+impl ServerMonitor {
+    /// 新建的监视器处于 Starting 状态，交给 `Server::start_with_monitor` 使用。
+    pub fn new() -> Self {
+        ServerMonitor {
+            inner: Arc::new(Inner {
+                state: Mutex::new(State {
+                    lifecycle: Lifecycle::Starting,
+                    started_at: None,
+                    address: None,
+                    last_error: None,
+                }),
+                total: AtomicU64::new(0),
+                in_flight: AtomicU64::new(0),
+                client_errors: AtomicU64::new(0),
+                server_errors: AtomicU64::new(0),
+                rejected: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    pub fn lifecycle(&self) -> Lifecycle {
+        self.inner.state.lock().unwrap().lifecycle.clone()
+    }
+
+    pub fn status(&self) -> ServerStatus {
+        let state = self.inner.state.lock().unwrap();
+        let counter = |c: &AtomicU64| c.load(Ordering::SeqCst);
+        ServerStatus {
+            lifecycle: state.lifecycle.clone(),
+            address: state.address,
+            uptime_secs: state.started_at.map(|t| t.elapsed().as_secs()),
+            requests: RequestStats {
+                total: counter(&self.inner.total),
+                in_flight: counter(&self.inner.in_flight),
+                client_errors: counter(&self.inner.client_errors),
+                server_errors: counter(&self.inner.server_errors),
+                rejected: counter(&self.inner.rejected),
+            },
+            last_error: state.last_error.clone(),
+        }
+    }
+
+    pub(crate) fn running(&self, address: SocketAddr) {
+        let mut state = self.inner.state.lock().unwrap();
+        state.lifecycle = Lifecycle::Running;
+        state.started_at = Some(Instant::now());
+        state.address = Some(address);
+    }
+
+    /// 只有运行中的服务会进入 Stopping，重复的停止信号不改变状态
+    pub(crate) fn stopping(&self) {
+        let mut state = self.inner.state.lock().unwrap();
+        if state.lifecycle == Lifecycle::Running {
+            state.lifecycle = Lifecycle::Stopping;
+        }
+    }
+
+    pub(crate) fn stopped(&self) {
+        let mut state = self.inner.state.lock().unwrap();
+        if !matches!(state.lifecycle, Lifecycle::Failed(_)) {
+            state.lifecycle = Lifecycle::Stopped;
+        }
+        state.started_at = None;
+        state.address = None;
+    }
+
+    pub(crate) fn failed(&self, reason: String) {
+        let mut state = self.inner.state.lock().unwrap();
+        state.lifecycle = Lifecycle::Failed(reason.clone());
+        state.started_at = None;
+        state.address = None;
+        state.last_error = Some(reason);
+    }
+
+    /// 记录不影响服务运行的错误，例如接收连接失败或 5xx 响应
+    pub(crate) fn record_error(&self, message: String) {
+        self.inner.state.lock().unwrap().last_error = Some(message);
+    }
+
+    pub(crate) fn request_started(&self) {
+        self.inner.total.fetch_add(1, Ordering::SeqCst);
+        self.inner.in_flight.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub(crate) fn request_finished(&self, status: u16) {
+        self.inner.in_flight.fetch_sub(1, Ordering::SeqCst);
+        match status {
+            400..=499 => self.inner.client_errors.fetch_add(1, Ordering::SeqCst),
+            500..=599 => self.inner.server_errors.fetch_add(1, Ordering::SeqCst),
+            _ => 0,
+        };
+    }
+
+    pub(crate) fn request_rejected(&self) {
+        self.inner.total.fetch_add(1, Ordering::SeqCst);
+        self.inner.rejected.fetch_add(1, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,318 @@
+mod common;
+
+use common::{TestServer, SEED_SQL};
+
+#[test]
+fn root_reports_running() {
+    let server = TestServer::start();
+    let response = server.get("/");
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, "User Data Server Running");
+}
+
+#[test]
+fn config_returns_json() {
+    let server = TestServer::start();
+    let response = server.get("/config");
+    assert_eq!(response.status, 200);
+    assert_eq!(response.header("Content-Type"), Some("application/json"));
+    let json = response.json();
+    assert_eq!(json["db_path"], server.config.db_path.as_str());
+    assert_eq!(json["port"], server.config.port);
+}
+
+#[test]
+fn query_by_phone() {
+    let server = TestServer::start();
+    let response = server.post_form("/query", "phone=13800000002");
+    assert_eq!(response.status, 200);
+    let json = response.json();
+    let users = json.as_array().unwrap();
+    assert_eq!(users.len(), 2);
+    assert!(users.iter().all(|u| u["phone"] == "13800000002"));
+}
+
+#[test]
+fn query_by_qq_and_email() {
+    let server = TestServer::start();
+
+    let json = server.post_form("/query", "qq=10001").json();
+    assert_eq!(json[0]["email"], "alice@example.com");
+
+    let json = server.post_form("/query", "email=bob@example.com").json();
+    assert_eq!(json[0]["qq"], "10002");
+}
+
+#[test]
+fn query_returns_null_for_missing_columns() {
+    let server = TestServer::start();
+    let json = server.post_form("/query", "email=carol@example.com").json();
+    assert_eq!(json[0]["qq"], serde_json::Value::Null);
+}
+
+#[test]
+fn query_without_match_is_empty() {
+    let server = TestServer::start();
+    let json = server.post_form("/query", "phone=00000000000").json();
+    assert_eq!(json, serde_json::json!([]));
+}
+
+#[test]
+fn query_without_recognized_field_is_bad_request() {
+    let server = TestServer::start();
+    for body in ["", "phone", "phone=", "&&&", "unknown=1", "=13800000001"] {
+        let response = server.post_form("/query", body);
+        assert_eq!(response.status, 400, "body {:?}", body);
+        assert_eq!(response.json()["error"]["code"], "missing_filter", "body {:?}", body);
+    }
+}
+
+#[test]
+fn multiple_fields_are_combined_with_and() {
+    let server = TestServer::start();
+
+    let json = server.post_form("/query", "phone=13800000002&email=carol%40example.com").json();
+    let users = json.as_array().unwrap();
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0]["email"], "carol@example.com");
+
+    let json = server.post_form("/query", "phone=13800000001&qq=10002").json();
+    assert_eq!(json, serde_json::json!([]));
+
+    let json = server.get("/api/v1/users?phone=13800000002&qq=10002&unknown=x").json();
+    assert_eq!(json["match"], "all");
+    assert_eq!(json["filters"], serde_json::json!({ "phone": "13800000002", "qq": "10002" }));
+    assert_eq!(json["items"].as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn query_decodes_percent_and_plus() {
+    let server = TestServer::start();
+
+    let json = server.post_form("/query", "email=alice%40example.com").json();
+    assert_eq!(json[0]["qq"], "10001");
+
+    let json = server.post_form("/query", "phone=+13800000001").json();
+    assert_eq!(json, serde_json::json!([]));
+}
+
+#[test]
+fn query_accepts_json_body() {
+    let server = TestServer::start();
+    let response = server.request("POST", "/query", &[("Content-Type", "application/json; charset=utf-8")], r#"{"qq": 10001}"#);
+    assert_eq!(response.status, 200);
+    assert_eq!(response.json()[0]["email"], "alice@example.com");
+}
+
+#[test]
+fn query_accepts_query_string_on_get() {
+    let server = TestServer::start();
+    let response = server.get("/query?email=bob%40example.com");
+    assert_eq!(response.status, 200);
+    assert_eq!(response.json()[0]["phone"], "13800000002");
+}
+
+#[test]
+fn unparseable_bodies_are_bad_requests() {
+    let server = TestServer::start();
+    assert_eq!(server.post_form("/query", "phone=%zz").status, 400);
+    assert_eq!(server.post_form("/query", "phone=%ff").status, 400);
+
+    let json = [("Content-Type", "application/json")];
+    assert_eq!(server.request("POST", "/query", &json, "{not json").status, 400);
+    assert_eq!(server.request("POST", "/query", &json, "[1, 2]").status, 400);
+    assert_eq!(server.request("POST", "/query", &json, r#"{"phone": ["1"]}"#).status, 400);
+
+    let xml = [("Content-Type", "application/xml")];
+    assert_eq!(server.request("POST", "/query", &xml, "<phone/>").status, 400);
+}
+
+#[test]
+fn stats_counts_records() {
+    let server = TestServer::start();
+    let response = server.post_form("/stats", "");
+    assert_eq!(response.status, 200);
+    assert!(response.body.contains("Total Records: 3"));
+    assert!(response.body.contains("Unique Phones: 2"));
+    assert!(response.body.contains("Unique QQs: 2"));
+    assert!(response.body.contains("Unique Emails: 3"));
+}
+
+#[test]
+fn unknown_paths_are_not_found() {
+    let server = TestServer::start();
+    assert_eq!(server.get("/missing").status, 404);
+    assert_eq!(server.post_form("/missing", "").status, 404);
+}
+
+#[test]
+fn unsupported_methods_are_rejected() {
+    let server = TestServer::start();
+    assert_eq!(server.request("PUT", "/query", &[], "phone=1").status, 405);
+    assert_eq!(server.request("DELETE", "/", &[], "").status, 405);
+}
+
+#[test]
+fn concurrent_queries_share_connection_pool() {
+    let server = TestServer::start_with(|config| {
+        config.workers = 8;
+        config.db_pool_size = 2;
+    });
+
+    std::thread::scope(|scope| {
+        for _ in 0..8 {
+            scope.spawn(|| {
+                for _ in 0..5 {
+                    let json = server.post_form("/query", "qq=10002").json();
+                    assert_eq!(json[0]["email"], "bob@example.com");
+                }
+            });
+        }
+    });
+}
+
+#[test]
+fn v1_routes_return_json() {
+    let server = TestServer::start();
+
+    assert_eq!(server.get("/api/v1/status").json()["status"], "running");
+    assert_eq!(server.get("/api/v1/config").json()["port"], server.config.port);
+
+    let json = server.post_form("/api/v1/query", "phone=13800000001").json();
+    assert_eq!(json["items"][0]["email"], "alice@example.com");
+
+    let json = server.get("/api/v1/stats").json();
+    assert_eq!(json["total_records"], 3);
+    assert_eq!(json["unique_phones"], 2);
+    assert_eq!(json["unique_qqs"], 2);
+    assert_eq!(json["unique_emails"], 3);
+}
+
+#[test]
+fn errors_are_structured_json() {
+    let server = TestServer::start();
+
+    let response = server.get("/api/v1/missing");
+    assert_eq!(response.status, 404);
+    assert_eq!(response.header("Content-Type"), Some("application/json"));
+    assert_eq!(response.json()["error"]["code"], "not_found");
+
+    let response = server.request("DELETE", "/api/v1/stats", &[], "");
+    assert_eq!(response.status, 405);
+    assert_eq!(response.header("Allow"), Some("GET"));
+    assert_eq!(response.json()["error"]["code"], "method_not_allowed");
+
+    let response = server.post_form("/api/v1/query", "phone=%zz");
+    assert_eq!(response.status, 400);
+    assert_eq!(response.json()["error"]["code"], "bad_request");
+    assert!(response.json()["error"]["message"].as_str().unwrap().contains("percent-encoding"));
+}
+
+#[test]
+fn oversized_bodies_are_rejected() {
+    let server = TestServer::start_with(|config| config.max_body_bytes = 16);
+
+    let response = server.post_form("/api/v1/query", &format!("phone={}", "1".repeat(32)));
+    assert_eq!(response.status, 413);
+    assert_eq!(response.json()["error"]["code"], "payload_too_large");
+
+    assert_eq!(server.post_form("/api/v1/query", "phone=1").status, 200);
+}
+
+#[test]
+fn database_errors_are_internal_errors() {
+    let server = TestServer::start();
+    rusqlite::Connection::open(&server.config.db_path)
+        .unwrap()
+        .execute_batch("DROP TABLE users")
+        .unwrap();
+
+    let response = server.get("/api/v1/stats");
+    assert_eq!(response.status, 500);
+    assert_eq!(response.json()["error"]["code"], "database_error");
+}
+
+#[test]
+fn get_lookup_by_query_parameter() {
+    let server = TestServer::start();
+
+    let response = server.get("/api/v1/users?phone=13800000002");
+    assert_eq!(response.status, 200);
+    assert!(response.header("Cache-Control").is_some());
+    assert_eq!(response.json()["items"].as_array().unwrap().len(), 2);
+
+    let json = server.get("/api/v1/users?email=alice%40example.com").json();
+    assert_eq!(json["items"][0]["qq"], "10001");
+
+    let json = server.get("/api/v1/users?qq=10002").json();
+    assert_eq!(json["items"][0]["email"], "bob@example.com");
+}
+
+#[test]
+fn get_lookup_by_path_parameter() {
+    let server = TestServer::start();
+
+    let json = server.get("/api/v1/users/qq/10001").json();
+    assert_eq!(json["items"][0]["phone"], "13800000001");
+
+    let json = server.get("/api/v1/users/email/carol%40example.com").json();
+    assert_eq!(json["items"][0]["phone"], "13800000002");
+
+    assert_eq!(server.get("/api/v1/users/name/alice").status, 404);
+    assert_eq!(server.post_form("/api/v1/users/qq/10001", "").status, 405);
+}
+
+#[test]
+fn path_parameter_keeps_plus_sign() {
+    let sql = format!("{}\nINSERT INTO users (email, phone, qq) VALUES ('dave@example.com', '+8613800000004', NULL);", SEED_SQL);
+    let server = TestServer::start_with_sql(&sql, |_| {});
+
+    for path in ["/api/v1/users/phone/+8613800000004", "/api/v1/users/phone/%2B8613800000004"] {
+        let json = server.get(path).json();
+        assert_eq!(json["items"][0]["email"], "dave@example.com", "path {}", path);
+    }
+    // 查询字符串仍按表单规则解码：`+` 是空格，需要写成 `%2B`
+    assert_eq!(server.get("/api/v1/users?phone=%2B8613800000004").json()["items"][0]["email"], "dave@example.com");
+    assert_eq!(server.get("/api/v1/users?phone=+8613800000004").json()["items"], serde_json::json!([]));
+}
+
+#[test]
+fn lookups_are_paginated_with_cursor() {
+    let server = TestServer::start();
+
+    let first = server.get("/api/v1/users?phone=13800000002&limit=1").json();
+    assert_eq!(first["items"].as_array().unwrap().len(), 1);
+    assert_eq!(first["items"][0]["email"], "bob@example.com");
+    assert_eq!(first["truncated"], true);
+    let cursor = first["next_cursor"].as_str().unwrap();
+
+    let second = server.get(&format!("/api/v1/users?phone=13800000002&limit=1&cursor={}", cursor)).json();
+    assert_eq!(second["items"][0]["email"], "carol@example.com");
+    assert_eq!(second["truncated"], false);
+    assert_eq!(second["next_cursor"], serde_json::Value::Null);
+}
+
+#[test]
+fn page_size_is_capped_by_config() {
+    let server = TestServer::start_with(|config| config.max_page_size = 1);
+
+    let json = server.get("/api/v1/users?phone=13800000002&limit=50").json();
+    assert_eq!(json["items"].as_array().unwrap().len(), 1);
+    assert_eq!(json["truncated"], true);
+
+    let json = server.post_form("/query", "phone=13800000002").json();
+    assert_eq!(json.as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn invalid_page_parameters_are_bad_requests() {
+    let server = TestServer::start();
+
+    let response = server.get("/api/v1/users?phone=13800000002&limit=0");
+    assert_eq!(response.status, 400);
+    assert_eq!(response.json()["error"]["code"], "invalid_limit");
+
+    let response = server.get("/api/v1/users?phone=13800000002&cursor=abc");
+    assert_eq!(response.status, 400);
+    assert_eq!(response.json()["error"]["code"], "invalid_cursor");
+}
@@ -0,0 +1,220 @@
+mod common;
+
+use common::{api_key, TestServer};
+use rusqlite::Connection;
+use std::process::Command;
+use userdata_rust::audit::{AuditLog, GENESIS_HASH};
+use userdata_rust::auth::Scope;
+use userdata_rust::config::Secret;
+
+fn audited_server() -> TestServer {
+    TestServer::start_with(|config| {
+        config.api_keys = vec![
+            api_key("app", "app-key", &[Scope::Read, Scope::Write]),
+            api_key("ops", "admin-key", &[Scope::Admin]),
+        ];
+        config.audit_secret = Some(Secret::new("audit-secret"));
+    })
+    .with_api_key("app-key")
+}
+
+// 审计数据库使用 WAL 模式，新写入的内容可能还在 -wal 文件中
+fn audit_files_contain(server: &TestServer, needle: &[u8]) -> bool {
+    let path = audit_path(server);
+    [path.clone(), format!("{}-wal", path)]
+        .iter()
+        .filter_map(|path| std::fs::read(path).ok())
+        .any(|raw| raw.windows(needle.len()).any(|w| w == needle))
+}
+
+fn audit_path(server: &TestServer) -> String {
+    AuditLog::path_for(&server.config)
+}
+
+#[test]
+fn data_access_is_recorded() {
+    let server = audited_server();
+    assert_eq!(server.get("/api/v1/users?phone=13800000002").status, 200);
+    assert_eq!(server.get("/api/v1/users/1").status, 200);
+    assert_eq!(server.post_form("/query", "email=nobody@example.com").status, 200);
+    let response = server.request(
+        "PATCH",
+        "/api/v1/users/2",
+        &[("Authorization", "Bearer app-key"), ("Content-Type", "application/json")],
+        r#"{"qq": "10009"}"#,
+    );
+    assert_eq!(response.status, 200);
+    // 未通过认证的尝试同样记录
+    assert_eq!(server.request("GET", "/api/v1/users/qq/10001", &[("Authorization", "Bearer wrong-key")], "").status, 401);
+    // 统计和状态接口不记录
+    assert_eq!(server.get("/api/v1/stats").status, 200);
+
+    let json = server.get_as("admin-key", "/api/v1/audit");
+    let items = json["items"].as_array().unwrap();
+    let summary = items
+        .iter()
+        .map(|i| (i["method"].as_str().unwrap(), i["route"].as_str().unwrap(), i["status"].as_u64().unwrap(), i["result_count"].as_i64()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        summary,
+        [
+            ("GET", "/api/v1/users", 200, Some(2)),
+            ("GET", "/api/v1/users/{id}", 200, Some(1)),
+            ("POST", "/query", 200, Some(0)),
+            ("PATCH", "/api/v1/users/{id}", 200, Some(1)),
+            ("GET", "/api/v1/users/{field}/{value}", 401, None),
+        ]
+    );
+    assert_eq!(items[0]["key_id"], "app");
+    assert!(items[4]["key_id"].is_null());
+    assert_eq!(items[0]["prev_hash"], GENESIS_HASH);
+    assert_eq!(items[1]["prev_hash"], items[0]["hash"]);
+
+    // 查询值只以 HMAC 摘要保存，密钥不在日志文件中
+    let audit = AuditLog::open_for(&server.config).unwrap();
+    let fields: serde_json::Value = serde_json::from_str(items[0]["fields"].as_str().unwrap()).unwrap();
+    assert_eq!(fields["phone"], audit.hash_value("phone", "13800000002").unwrap());
+    let fields: serde_json::Value = serde_json::from_str(items[3]["fields"].as_str().unwrap()).unwrap();
+    assert_eq!(fields["id"], audit.hash_value("id", "2").unwrap());
+    assert_eq!(fields["qq"], audit.hash_value("qq", "10009").unwrap());
+    assert!(!audit_files_contain(&server, b"13800000002"));
+    assert!(!audit_files_contain(&server, b"audit-secret"));
+    assert!(server.get_as("admin-key", "/api/v1/config").get("audit_secret").is_none());
+
+    let json = server.get_as("admin-key", "/api/v1/audit/verify");
+    assert_eq!(json["ok"], true);
+    assert_eq!(json["entries"], 5);
+    assert_eq!(json["last_hash"], items[4]["hash"]);
+
+    assert_eq!(server.get("/api/v1/audit").status, 403);
+}
+
+#[test]
+fn audit_export_is_paginated() {
+    let server = audited_server();
+    for qq in ["10001", "10002", "10003"] {
+        server.get(&format!("/api/v1/users/qq/{}", qq));
+    }
+
+    let json = server.get_as("admin-key", "/api/v1/audit?limit=2");
+    assert_eq!(json["items"].as_array().unwrap().len(), 2);
+    assert_eq!(json["next_cursor"], "2");
+    let json = server.get_as("admin-key", "/api/v1/audit?limit=2&cursor=2");
+    assert_eq!(json["items"][0]["seq"], 3);
+}
+
+#[test]
+fn tampering_is_detected() {
+    let server = audited_server();
+    for qq in ["10001", "10002", "10003"] {
+        server.get(&format!("/api/v1/users/qq/{}", qq));
+    }
+
+    let conn = Connection::open(audit_path(&server)).unwrap();
+    conn.execute("UPDATE audit_log SET status = 404 WHERE seq = 2", []).unwrap();
+    let response = server.request("GET", "/api/v1/audit/verify", &[("Authorization", "Bearer admin-key")], "");
+    assert_eq!(response.status, 409);
+    assert_eq!(response.json()["first_invalid_seq"], 2);
+    assert_eq!(response.json()["entries"], 1);
+
+    // 删除中间记录也会断链
+    conn.execute("UPDATE audit_log SET status = 200 WHERE seq = 2", []).unwrap();
+    assert!(AuditLog::open_for(&server.config).unwrap().verify().unwrap().ok);
+    conn.execute("DELETE FROM audit_log WHERE seq = 2", []).unwrap();
+    let verification = AuditLog::open_for(&server.config).unwrap().verify().unwrap();
+    assert!(!verification.ok);
+    assert_eq!(verification.first_invalid_seq, Some(3));
+}
+
+#[test]
+fn values_are_not_recorded_without_secret() {
+    let server = TestServer::start();
+    assert_eq!(server.get("/api/v1/users?phone=13800000002").status, 200);
+
+    let json = server.get("/api/v1/audit");
+    let fields: serde_json::Value = serde_json::from_str(json.json()["items"][0]["fields"].as_str().unwrap()).unwrap();
+    assert_eq!(fields, serde_json::json!({ "phone": null }));
+    assert!(AuditLog::open_for(&server.config).unwrap().hash_value("phone", "13800000002").is_none());
+}
+
+#[test]
+fn failed_writes_record_no_result_count() {
+    let server = audited_server();
+    let json = [("Content-Type", "application/json")];
+    assert_eq!(server.request("DELETE", "/api/v1/users/999", &[], "").status, 404);
+    assert_eq!(server.request("PATCH", "/api/v1/users/1", &json, r#"{"email": "not-an-email"}"#).status, 400);
+    assert_eq!(server.request("PATCH", "/api/v1/users/1", &json, r#"{"qq": "10009"}"#).status, 200);
+
+    let json = server.get_as("admin-key", "/api/v1/audit");
+    let summary = json["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|i| (i["method"].as_str().unwrap(), i["status"].as_u64().unwrap(), i["result_count"].as_i64()))
+        .collect::<Vec<_>>();
+    assert_eq!(summary, [("DELETE", 404, None), ("PATCH", 400, None), ("PATCH", 200, Some(1))]);
+}
+
+#[test]
+fn writes_are_rolled_back_when_audit_fails() {
+    let server = audited_server();
+    let users = |conn: &Connection| {
+        conn.prepare("SELECT rowid, qq FROM users ORDER BY rowid")
+            .unwrap()
+            .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Option<String>>(1)?)))
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap()
+    };
+    let data = Connection::open(&server.config.db_path).unwrap();
+    let before = users(&data);
+
+    // 让审计库拒绝一切新记录
+    let audit = Connection::open(audit_path(&server)).unwrap();
+    audit
+        .execute_batch("CREATE TRIGGER fail BEFORE INSERT ON audit_log BEGIN SELECT RAISE(ABORT, 'audit unavailable'); END;")
+        .unwrap();
+    let json = [("Content-Type", "application/json")];
+    for response in [
+        server.request("POST", "/api/v1/users", &json, r#"{"qq": "10009"}"#),
+        server.request("PATCH", "/api/v1/users/1", &json, r#"{"qq": "10009"}"#),
+        server.request("PUT", "/api/v1/users/1", &json, r#"{"qq": "10009"}"#),
+        server.request("DELETE", "/api/v1/users/2", &[], ""),
+    ] {
+        assert_eq!(response.status, 500);
+        assert_eq!(response.json()["error"]["code"], "audit_failed");
+    }
+    assert_eq!(users(&data), before);
+
+    audit.execute_batch("DROP TRIGGER fail;").unwrap();
+    let verification = AuditLog::open_for(&server.config).unwrap().verify().unwrap();
+    assert!(verification.ok);
+    assert_eq!(verification.entries, 0);
+}
+
+#[test]
+fn cli_exports_and_verifies_audit_log() {
+    let server = audited_server();
+    server.get("/api/v1/users/1");
+    server.get("/api/v1/users/2");
+    let run = |flag: &str| {
+        Command::new(env!("CARGO_BIN_EXE_userdata-server"))
+            .args(["--db-path", &server.config.db_path, flag])
+            .output()
+            .unwrap()
+    };
+
+    let output = run("--export-audit");
+    assert!(output.status.success());
+    let lines = String::from_utf8(output.stdout).unwrap();
+    let entries = lines.lines().map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap()).collect::<Vec<_>>();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[1]["route"], "/api/v1/users/{id}");
+
+    assert!(run("--verify-audit").status.success());
+    Connection::open(audit_path(&server))
+        .unwrap()
+        .execute("UPDATE audit_log SET key_id = 'someone-else' WHERE seq = 1", [])
+        .unwrap();
+    assert!(!run("--verify-audit").status.success());
+}
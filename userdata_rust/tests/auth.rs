@@ -0,0 +1,84 @@
+mod common;
+
+use common::{api_key, TestServer};
+use userdata_rust::auth::{self, Scope};
+
+fn keyed_server() -> TestServer {
+    TestServer::start_with(|config| {
+        config.api_keys = vec![
+            api_key("reader", "read-key", &[Scope::Read]),
+            api_key("ops", "admin-key", &[Scope::Admin]),
+        ]
+    })
+}
+
+#[test]
+fn requests_without_valid_key_are_rejected() {
+    let server = keyed_server();
+
+    for path in ["/", "/api/v1/query?phone=13800000001", "/api/v1/users/1", "/api/v1/status"] {
+        let response = server.get(path);
+        assert_eq!(response.status, 401, "path {}", path);
+        assert_eq!(response.header("WWW-Authenticate"), Some("Bearer"));
+        assert_eq!(response.json()["error"]["code"], "unauthorized");
+    }
+
+    let response = server.request("GET", "/api/v1/users/1", &[("Authorization", "Bearer wrong-key")], "");
+    assert_eq!(response.status, 401);
+    let response = server.request("GET", "/api/v1/users/1", &[("Authorization", "read-key")], "");
+    assert_eq!(response.status, 401);
+}
+
+#[test]
+fn scopes_limit_endpoints() {
+    let reader = keyed_server().with_api_key("read-key");
+    assert_eq!(reader.get("/api/v1/users/1").json()["email"], "alice@example.com");
+    assert_eq!(reader.post_form("/query", "qq=10002").status, 200);
+    assert_eq!(reader.post_form("/stats", "").status, 200);
+
+    for path in ["/config", "/api/v1/config", "/api/v1/health/db"] {
+        let response = reader.get(path);
+        assert_eq!(response.status, 403, "path {}", path);
+        assert_eq!(response.json()["error"]["code"], "insufficient_scope");
+        assert_eq!(response.json()["error"]["message"], "API key 'reader' lacks the admin scope");
+    }
+
+    let admin = keyed_server().with_api_key("admin-key");
+    assert_eq!(admin.get("/api/v1/users/1").status, 200);
+    assert_eq!(admin.get("/api/v1/health/db").status, 200);
+    let response = admin.get("/config");
+    assert_eq!(response.status, 200);
+    assert!(response.json().get("api_keys").is_none());
+    assert!(!response.body.contains(&auth::hash_key("admin-key")));
+}
+
+#[test]
+fn endpoints_are_closed_without_keys() {
+    let server = TestServer::start_with(|config| config.api_keys.clear());
+    for path in ["/", "/config", "/api/v1/users/1", "/api/v1/query?phone=13800000001", "/api/v1/health/db", "/api/v1/audit"] {
+        let response = server.request("GET", path, &[], "");
+        assert_eq!(response.status, 403, "path {}", path);
+        assert_eq!(response.json()["error"]["code"], "api_keys_required");
+    }
+    assert!(!server.get("/config").body.contains(&server.config.db_path));
+}
+
+#[test]
+fn keys_are_read_from_config_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    let content = format!(
+        "[[api_keys]]\nid = \"app\"\nsha256 = \"{}\"\nscopes = [\"read\", \"write\"]\n",
+        auth::hash_key("secret").to_uppercase()
+    );
+    std::fs::write(&path, content).unwrap();
+
+    let config = userdata_rust::ServerConfig::from_file(&path).unwrap();
+    let server = TestServer::start_with(|c| c.api_keys = config.api_keys).with_api_key("secret");
+    assert_eq!(server.get("/api/v1/users/2").json()["qq"], "10002");
+}
+
+#[test]
+fn hash_key_is_sha256_hex() {
+    assert_eq!(auth::hash_key("abc"), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+}
@@ -0,0 +1,223 @@
+//! 集成测试公共工具：临时数据库、测试服务和最小 HTTP 客户端。
+
+#![allow(dead_code)]
+
+use rusqlite::Connection;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+use userdata_rust::auth::{self, ApiKey, Scope};
+use userdata_rust::status::ServerMonitor;
+use userdata_rust::{Lifecycle, Server, ServerConfig, ServerHandle, ServerStatus};
+
+pub struct TestServer {
+    pub config: ServerConfig,
+    handle: Option<ServerHandle>,
+    monitor: ServerMonitor,
+    /// 请求未指定 Authorization 头时默认携带的值
+    authorization: Option<String>,
+    _dir: TempDir,
+}
+
+impl TestServer {
+    /// 使用默认测试数据启动服务。
+    pub fn start() -> Self {
+        Self::start_with(|_| {})
+    }
+
+    /// 启动服务前允许调整配置。
+    pub fn start_with(configure: impl FnOnce(&mut ServerConfig)) -> Self {
+        Self::start_with_sql(SEED_SQL, configure)
+    }
+
+    /// 用给定 SQL 初始化数据库后启动服务。
+    ///
+    /// 默认配置一个 admin 密钥 `TEST_KEY` 并在请求中携带；`configure` 可替换 `api_keys`。
+    pub fn start_with_sql(sql: &str, configure: impl FnOnce(&mut ServerConfig)) -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = create_database(&dir, sql);
+
+        let mut config = ServerConfig {
+            db_path: db_path.to_string_lossy().to_string(),
+            port: 0,
+            api_keys: vec![api_key("test", TEST_KEY, &[Scope::Admin])],
+            ..ServerConfig::default()
+        };
+        configure(&mut config);
+
+        // 端口 0 由系统分配，句柄中的配置记录实际端口
+        let handle = Server::start(config).unwrap();
+        let config = handle.config().clone();
+        let monitor = handle.monitor();
+
+        Self {
+            config,
+            handle: Some(handle),
+            monitor,
+            authorization: Some(format!("Bearer {}", TEST_KEY)),
+            _dir: dir,
+        }
+    }
+
+    /// 之后的请求默认使用该密钥认证。
+    pub fn with_api_key(mut self, key: &str) -> Self {
+        self.authorization = Some(format!("Bearer {}", key));
+        self
+    }
+
+    /// 之后的请求默认不带 Authorization 头。
+    pub fn without_api_key(mut self) -> Self {
+        self.authorization = None;
+        self
+    }
+
+    pub fn get(&self, path: &str) -> HttpResponse {
+        self.request("GET", path, &[], "")
+    }
+
+    pub fn post_form(&self, path: &str, body: &str) -> HttpResponse {
+        self.request("POST", path, &[("Content-Type", "application/x-www-form-urlencoded")], body)
+    }
+
+    /// 用指定密钥发送 GET 请求并解析 JSON 响应。
+    pub fn get_as(&self, key: &str, path: &str) -> serde_json::Value {
+        let auth = format!("Bearer {}", key);
+        self.request("GET", path, &[("Authorization", &auth)], "").json()
+    }
+
+    pub fn connect(&self) -> TcpStream {
+        let stream = TcpStream::connect(("127.0.0.1", self.config.port)).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        stream
+    }
+
+    pub fn request(&self, method: &str, path: &str, headers: &[(&str, &str)], body: &str) -> HttpResponse {
+        let mut stream = self.connect();
+
+        let mut raw = format!(
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\nContent-Length: {}\r\n",
+            method,
+            path,
+            body.len()
+        );
+        for (name, value) in headers {
+            raw.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        if let Some(value) = &self.authorization {
+            if !headers.iter().any(|(name, _)| name.eq_ignore_ascii_case("Authorization")) {
+                raw.push_str(&format!("Authorization: {}\r\n", value));
+            }
+        }
+        raw.push_str("\r\n");
+        raw.push_str(body);
+        stream.write_all(raw.as_bytes()).unwrap();
+
+        read_response(&mut stream)
+    }
+}
+
+impl TestServer {
+    /// 停止后仍可查询最终状态。
+    pub fn status(&self) -> ServerStatus {
+        self.monitor.status()
+    }
+
+    /// 停止服务并返回最终状态。
+    pub fn stop(&mut self) -> Lifecycle {
+        if let Some(mut handle) = self.handle.take() {
+            handle.stop();
+        }
+        self.monitor.lifecycle()
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+/// 以明文密钥构造配置项，配置中只保存摘要。
+pub fn api_key(id: &str, key: &str, scopes: &[Scope]) -> ApiKey {
+    ApiKey {
+        id: id.to_string(),
+        sha256: auth::hash_key(key),
+        scopes: scopes.to_vec(),
+        role: None,
+        masking: None,
+    }
+}
+
+pub fn read_response(stream: &mut TcpStream) -> HttpResponse {
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    HttpResponse::parse(&response)
+}
+
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl HttpResponse {
+    fn parse(raw: &str) -> Self {
+        let (head, body) = raw.split_once("\r\n\r\n").unwrap_or((raw, ""));
+        let mut lines = head.lines();
+        let status = lines
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .and_then(|code| code.parse().ok())
+            .unwrap();
+        let headers = lines
+            .filter_map(|line| line.split_once(':'))
+            .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+            .collect();
+        Self {
+            status,
+            headers,
+            body: body.to_string(),
+        }
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_str(&self.body).unwrap_or_else(|e| panic!("invalid JSON {:?}: {}", self.body, e))
+    }
+}
+
+/// `TestServer` 默认配置的 admin 密钥
+pub const TEST_KEY: &str = "test-admin-key";
+
+pub const SEED_SQL: &str = "
+    CREATE TABLE users (email TEXT, phone TEXT, qq TEXT);
+    INSERT INTO users (email, phone, qq) VALUES
+        ('alice@example.com', '13800000001', '10001'),
+        ('bob@example.com', '13800000002', '10002'),
+        ('carol@example.com', '13800000002', NULL);";
+
+fn create_database(dir: &TempDir, sql: &str) -> PathBuf {
+    let path = dir.path().join("user_data.db");
+    Connection::open(&path).unwrap().execute_batch(sql).unwrap();
+    path
+}
+
+/// 监听线程在服务停止后异步退出，需要稍等端口才会关闭。
+pub fn wait_for_port_closed(port: u16) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < deadline {
+        if TcpStream::connect(("127.0.0.1", port)).is_err() {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    panic!("server still listening on port {}", port);
+}
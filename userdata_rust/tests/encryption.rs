@@ -0,0 +1,90 @@
+mod common;
+
+use userdata_rust::config::Secret;
+use userdata_rust::ServerConfig;
+
+#[test]
+fn db_key_is_never_exposed() {
+    let config: ServerConfig = serde_json::from_str(r#"{"db_path": "user_data.db", "db_key": "correct horse"}"#).unwrap();
+    assert_eq!(config.db_key.as_ref().map(Secret::expose), Some("correct horse"));
+    assert!(!format!("{:?}", config).contains("correct horse"));
+    assert!(serde_json::to_value(&config).unwrap().get("db_key").is_none());
+}
+
+#[cfg(not(feature = "sqlcipher"))]
+#[test]
+fn db_key_requires_sqlcipher_feature() {
+    use userdata_rust::{Server, StartError};
+
+    let config = ServerConfig {
+        db_key: Some(Secret::new("correct horse")),
+        ..ServerConfig::default()
+    };
+    assert_eq!(config.validate().unwrap_err().to_string(), "Invalid config: db_key requires a build with the sqlcipher feature");
+    let err = Server::start(config).err().unwrap();
+    assert!(matches!(err, StartError::Config(_)));
+    assert_eq!(err.code(), "invalid_config");
+}
+
+#[cfg(not(feature = "sqlcipher"))]
+#[test]
+fn cli_commands_reject_db_key_without_sqlcipher() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("user_data.db");
+    let output_path = dir.path().join("encrypted.db");
+    let encrypt = ["--encrypt-db", output_path.to_str().unwrap()];
+    for command in [&["--migrate-only"][..], &encrypt, &["--export-audit"], &["--verify-audit"]] {
+        let output = std::process::Command::new(env!("CARGO_BIN_EXE_userdata-server"))
+            .arg("--db-path")
+            .arg(&db_path)
+            .args(command)
+            .env("USERDATA_DB_KEY", "correct horse")
+            .output()
+            .unwrap();
+        assert!(!output.status.success(), "{:?}", command);
+        assert!(String::from_utf8_lossy(&output.stderr).contains("db_key requires a build with the sqlcipher feature"));
+    }
+    // 校验失败时不会创建数据库或审计文件
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+}
+
+#[cfg(feature = "sqlcipher")]
+mod sqlcipher {
+    use super::common::TestServer;
+    use rusqlite::Connection;
+    use userdata_rust::audit::AuditLog;
+    use userdata_rust::config::Secret;
+    use userdata_rust::{db, Server};
+
+    // 把测试数据库加密成新文件，并让服务改用加密后的文件
+    fn encrypted_server(key: &str) -> TestServer {
+        TestServer::start_with(|config| {
+            let encrypted = format!("{}.enc", config.db_path);
+            db::encrypt_database(&config.db_path, &encrypted, "correct horse").unwrap();
+            config.db_path = encrypted;
+            config.db_key = Some(Secret::new(key));
+        })
+    }
+
+    #[test]
+    fn serves_encrypted_database_with_key() {
+        let server = encrypted_server("correct horse");
+        assert_eq!(server.get("/query?phone=13800000001").json()[0]["email"], "alice@example.com");
+        assert!(server.get("/api/v1/config").json().get("db_key").is_none());
+
+        // 审计日志用同一密钥加密
+        let audit = Connection::open(AuditLog::path_for(&server.config)).unwrap();
+        assert!(audit.query_row("SELECT count(*) FROM audit_log", [], |row| row.get::<_, i64>(0)).is_err());
+        assert_eq!(AuditLog::open_for(&server.config).unwrap().verify().unwrap().entries, 1);
+    }
+
+    #[test]
+    fn encrypted_database_is_unreadable_without_key() {
+        let server = encrypted_server("correct horse");
+        let mut config = server.config.clone();
+        config.db_key = Some(Secret::new("wrong key"));
+        assert!(Server::start(config.clone()).is_err());
+        config.db_key = None;
+        assert!(Server::start(config).is_err());
+    }
+}
@@ -0,0 +1,66 @@
+mod common;
+
+use common::TestServer;
+use rusqlite::Connection;
+use userdata_rust::health;
+use userdata_rust::migrations;
+use userdata_rust::schema::SchemaMapping;
+
+#[test]
+fn health_endpoint_reports_database_state() {
+    let server = TestServer::start();
+
+    let response = server.get("/api/v1/health/db");
+    assert_eq!(response.status, 200);
+    let json = response.json();
+    assert_eq!(json["ok"], true);
+    assert_eq!(json["integrity"]["mode"], "quick_check");
+    assert_eq!(json["integrity"]["ok"], true);
+    assert_eq!(json["schema"]["ok"], true);
+    assert_eq!(json["schema_version"], migrations::LATEST_VERSION);
+    assert_eq!(json["record_count"], 3);
+    assert_eq!(json["journal_mode"], "wal");
+    assert!(json["page_size"].as_i64().unwrap() > 0);
+    assert!(json["file_size_bytes"].as_u64().unwrap() > 0);
+    assert_eq!(json["sqlite_version"], rusqlite::version());
+    let indexes = json["indexes"].as_array().unwrap();
+    assert!(indexes.iter().all(|i| !i["index"].is_null()));
+
+    let json = server.get("/api/v1/health/db?full=true").json();
+    assert_eq!(json["integrity"]["mode"], "integrity_check");
+    assert_eq!(json["ok"], true);
+
+    assert_eq!(server.post_form("/api/v1/health/db", "").status, 405);
+}
+
+#[test]
+fn schema_problems_fail_the_check() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("contacts.db");
+    let path = path.to_str().unwrap();
+    Connection::open(path)
+        .unwrap()
+        .execute_batch("CREATE TABLE contacts (mail TEXT);")
+        .unwrap();
+
+    let mapping = SchemaMapping {
+        table: "contacts".to_string(),
+        email: "mail".to_string(),
+        ..SchemaMapping::default()
+    };
+    let report = health::check_path(path, None, &mapping, false).unwrap();
+    assert!(!report.ok);
+    assert!(report.integrity.ok);
+    assert_eq!(report.schema.error.as_deref(), Some("Table 'contacts' is missing columns: phone, qq"));
+    assert_eq!(report.record_count, None);
+    assert!(report.indexes.is_empty());
+}
+
+#[test]
+fn missing_database_is_not_created() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("missing.db");
+
+    assert!(health::check_path(path.to_str().unwrap(), None, &SchemaMapping::default(), false).is_err());
+    assert!(!path.exists());
+}
@@ -0,0 +1,312 @@
+mod common;
+
+use common::{read_response, wait_for_port_closed, TestServer, SEED_SQL, TEST_KEY};
+use rusqlite::Connection;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+use userdata_rust::schema::SchemaMapping;
+use userdata_rust::status::ServerMonitor;
+use userdata_rust::{Lifecycle, Server, ServerConfig, ServerStatus, StartError};
+
+// 请求体超过 1KB 时 tiny_http 不会预读；只发送一部分，让工作线程阻塞在读取上模拟慢请求
+fn start_slow_request(server: &TestServer) -> (TcpStream, String) {
+    let body = format!("phone=13800000001&padding={}", "x".repeat(2000));
+    let mut slow = server.connect();
+    write!(
+        slow,
+        "POST /query HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\nAuthorization: Bearer {}\r\nContent-Length: {}\r\n\r\n{}",
+        TEST_KEY,
+        body.len(),
+        &body[..100]
+    )
+    .unwrap();
+    std::thread::sleep(Duration::from_millis(200));
+    (slow, body[100..].to_string())
+}
+
+#[test]
+fn stop_returns_promptly_and_closes_listener() {
+    let mut server = TestServer::start();
+    assert_eq!(server.get("/").status, 200);
+
+    let started = Instant::now();
+    server.stop();
+    assert!(started.elapsed() < Duration::from_secs(1));
+    wait_for_port_closed(server.config.port);
+}
+
+#[test]
+fn idle_server_stops_without_requests() {
+    let mut server = TestServer::start();
+    std::thread::sleep(Duration::from_millis(50));
+
+    let started = Instant::now();
+    server.stop();
+    assert!(started.elapsed() < Duration::from_secs(1));
+    wait_for_port_closed(server.config.port);
+}
+
+#[test]
+fn saturated_pool_returns_service_unavailable() {
+    let server = TestServer::start_with(|config| {
+        config.workers = 1;
+        config.queue_capacity = 0;
+    });
+
+    let (mut slow, tail) = start_slow_request(&server);
+
+    let response = server.get("/");
+    assert_eq!(response.status, 503);
+    assert_eq!(response.header("Retry-After"), Some("1"));
+
+    slow.write_all(tail.as_bytes()).unwrap();
+    let response = read_response(&mut slow);
+    assert_eq!(response.status, 200);
+    assert_eq!(response.json()[0]["qq"], "10001");
+    assert_eq!(server.status().requests.rejected, 1);
+}
+
+#[test]
+fn stop_drains_in_flight_requests() {
+    let mut server = TestServer::start();
+    let (mut slow, tail) = start_slow_request(&server);
+
+    let client = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(300));
+        slow.write_all(tail.as_bytes()).unwrap();
+        read_response(&mut slow)
+    });
+
+    assert_eq!(server.stop(), Lifecycle::Stopped);
+    let response = client.join().unwrap();
+    assert_eq!(response.status, 200);
+    assert_eq!(response.json()[0]["qq"], "10001");
+}
+
+#[test]
+fn stop_gives_up_after_deadline() {
+    let mut server = TestServer::start_with(|config| config.shutdown_timeout_ms = 200);
+    let (_slow, _tail) = start_slow_request(&server);
+
+    let started = Instant::now();
+    let state = server.stop();
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert_eq!(
+        state,
+        Lifecycle::Failed("Shutdown deadline of 200ms exceeded with 1 requests in flight".to_string())
+    );
+    assert_eq!(server.status().last_error.as_deref(), Some("Shutdown deadline of 200ms exceeded with 1 requests in flight"));
+    wait_for_port_closed(server.config.port);
+}
+
+#[test]
+fn start_reports_bound_address() {
+    let server = TestServer::start();
+    assert_ne!(server.config.port, 0);
+    // 返回时已在监听，无需等待
+    assert_eq!(server.get("/").status, 200);
+}
+
+#[test]
+fn status_tracks_lifecycle_and_requests() {
+    let mut server = TestServer::start();
+    let status = server.status();
+    assert_eq!(status.lifecycle, Lifecycle::Running);
+    assert_eq!(status.address.unwrap().port(), server.config.port);
+    assert!(status.uptime_secs.is_some());
+
+    assert_eq!(server.get("/").status, 200);
+    assert_eq!(server.get("/missing").status, 404);
+    assert_eq!(server.get("/api/v1/query").status, 400);
+
+    let json = server.get("/api/v1/status").json();
+    assert_eq!(json["status"], "running");
+    assert_eq!(json["address"], format!("127.0.0.1:{}", server.config.port));
+    assert_eq!(json["requests"]["total"], 4);
+    assert_eq!(json["requests"]["in_flight"], 1);
+    assert_eq!(json["requests"]["client_errors"], 2);
+    assert!(json["last_error"].is_null());
+
+    server.stop();
+    let status = server.status();
+    assert_eq!(status.lifecycle, Lifecycle::Stopped);
+    assert_eq!(status.address, None);
+    assert_eq!(status.uptime_secs, None);
+    assert_eq!(status.requests.total, 4);
+    assert_eq!(status.requests.in_flight, 0);
+}
+
+#[test]
+fn server_errors_are_recorded() {
+    let server = TestServer::start();
+    rusqlite::Connection::open(&server.config.db_path)
+        .unwrap()
+        .execute_batch("DROP TABLE users")
+        .unwrap();
+
+    assert_eq!(server.get("/api/v1/stats").status, 500);
+    let status = server.status();
+    assert_eq!(status.requests.server_errors, 1);
+    assert!(status.last_error.unwrap().starts_with("database_error"));
+}
+
+#[test]
+fn idle_status_reports_start_failure() {
+    let json = serde_json::to_value(ServerStatus::idle(Some("boom".to_string()))).unwrap();
+    assert_eq!(json["status"], "failed");
+    assert_eq!(json["reason"], "boom");
+    assert_eq!(json["last_error"], "boom");
+
+    let json = serde_json::to_value(ServerStatus::idle(None)).unwrap();
+    assert_eq!(json["status"], "stopped");
+    assert!(json.get("reason").is_none());
+}
+
+#[test]
+fn start_reports_bind_failure() {
+    let server = TestServer::start();
+    let config = ServerConfig {
+        port: server.config.port,
+        ..server.config.clone()
+    };
+
+    let err = Server::start(config).err().unwrap();
+    assert!(matches!(err, StartError::Bind { .. }));
+    assert_eq!(err.code(), "bind_failed");
+}
+
+#[test]
+fn start_reports_database_failures() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = ServerConfig {
+        db_path: dir.path().join("missing/user_data.db").to_string_lossy().to_string(),
+        port: 0,
+        ..ServerConfig::default()
+    };
+    let err = Server::start(config).err().unwrap();
+    assert!(matches!(err, StartError::Migration(_)));
+
+    let server = TestServer::start();
+    let config = ServerConfig {
+        port: 0,
+        schema: SchemaMapping {
+            table: "contacts".to_string(),
+            ..SchemaMapping::default()
+        },
+        ..server.config.clone()
+    };
+    let err = Server::start(config).err().unwrap();
+    assert!(matches!(err, StartError::Schema(_)));
+    assert_eq!(err.to_string(), "Database schema does not match configuration: Table 'contacts' does not exist");
+}
+
+#[test]
+fn status_is_starting_while_startup_is_in_progress() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("user_data.db");
+    let conn = Connection::open(&db_path).unwrap();
+    conn.execute_batch(SEED_SQL).unwrap();
+    // 持有排他锁，启动会阻塞在迁移上
+    conn.execute_batch("BEGIN EXCLUSIVE").unwrap();
+
+    let config = ServerConfig {
+        db_path: db_path.to_string_lossy().to_string(),
+        port: 0,
+        ..ServerConfig::default()
+    };
+    let monitor = ServerMonitor::new();
+    let starting = {
+        let monitor = monitor.clone();
+        std::thread::spawn(move || Server::start_with_monitor(config, monitor))
+    };
+    std::thread::sleep(Duration::from_millis(200));
+    assert!(!starting.is_finished());
+    let status = monitor.status();
+    assert_eq!(status.lifecycle, Lifecycle::Starting);
+    assert_eq!(serde_json::to_value(&status).unwrap()["status"], "starting");
+
+    conn.execute_batch("COMMIT").unwrap();
+    let mut handle = starting.join().unwrap().unwrap();
+    assert_eq!(monitor.lifecycle(), Lifecycle::Running);
+    assert_eq!(handle.stop(), Lifecycle::Stopped);
+}
+
+// 建好测试数据库和配置了 `TEST_KEY` 的配置文件，返回配置文件路径
+#[cfg(unix)]
+fn binary_config(dir: &tempfile::TempDir) -> String {
+    let db_path = dir.path().join("user_data.db");
+    Connection::open(&db_path).unwrap().execute_batch(SEED_SQL).unwrap();
+    let config = serde_json::json!({
+        "db_path": db_path,
+        "port": 0,
+        "api_keys": [{"id": "test", "sha256": userdata_rust::auth::hash_key(TEST_KEY), "scopes": ["admin"]}],
+    });
+    let config_path = dir.path().join("config.json");
+    std::fs::write(&config_path, config.to_string()).unwrap();
+    config_path.to_string_lossy().to_string()
+}
+
+// 启动服务程序，从日志中读出实际监听地址
+#[cfg(unix)]
+fn spawn_binary(config_path: &str, extra: &[&str]) -> (Child, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_userdata-server"))
+        .args(["--config", config_path])
+        .args(extra)
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut lines = BufReader::new(child.stderr.take().unwrap()).lines();
+    let address = loop {
+        let line = lines.next().expect("server exited before listening").unwrap();
+        if let Some((_, address)) = line.split_once("Server started on ") {
+            break address.trim().to_string();
+        }
+    };
+    // 继续读取日志，避免管道写满阻塞服务进程
+    std::thread::spawn(move || lines.for_each(drop));
+    (child, address)
+}
+
+#[cfg(unix)]
+fn send_sigterm(child: &Child) {
+    let status = Command::new("kill").args(["-TERM", &child.id().to_string()]).status().unwrap();
+    assert!(status.success());
+}
+
+#[cfg(unix)]
+#[test]
+fn binary_stops_cleanly_on_sigterm() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = binary_config(&dir);
+    let (mut child, address) = spawn_binary(&config_path, &[]);
+    assert!(TcpStream::connect(&address).is_ok());
+    send_sigterm(&child);
+    assert!(child.wait().unwrap().success());
+    assert!(TcpStream::connect(&address).is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn binary_exits_non_zero_when_drain_deadline_is_exceeded() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = binary_config(&dir);
+    let (mut child, address) = spawn_binary(&config_path, &["--shutdown-timeout-ms", "100"]);
+    // 只发送部分请求体，请求一直处于处理中
+    let body = format!("phone=13800000001&padding={}", "x".repeat(2000));
+    let mut slow = TcpStream::connect(&address).unwrap();
+    write!(
+        slow,
+        "POST /query HTTP/1.1\r\nHost: localhost\r\nAuthorization: Bearer {}\r\nContent-Length: {}\r\n\r\n{}",
+        TEST_KEY,
+        body.len(),
+        &body[..100]
+    )
+    .unwrap();
+    std::thread::sleep(Duration::from_millis(200));
+
+    send_sigterm(&child);
+    // 由服务返回的失败退出码，而不是被信号终止
+    assert_eq!(child.wait().unwrap().code(), Some(1));
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use common::{api_key, TestServer};
+use userdata_rust::access::Role;
+use userdata_rust::auth::Scope;
+use userdata_rust::masking::{mask_email, mask_phone, mask_qq, Masking};
+
+#[test]
+fn partial_masks_keep_recognisable_parts() {
+    assert_eq!(mask_phone("13812345678"), "138****5678");
+    assert_eq!(mask_email("alice@example.com"), "a***@example.com");
+    assert_eq!(mask_email("@example.com"), "***@example.com");
+    assert_eq!(mask_qq("123456789"), "12*****89");
+    assert_eq!(mask_qq("10001"), "10*01");
+
+    // 太短的值整体隐藏
+    assert_eq!(mask_phone("1234567"), "*******");
+    assert_eq!(mask_qq("1"), "*");
+
+    assert_eq!(Masking::Full.apply("email", "alice@example.com"), "****");
+    assert_eq!(Masking::None.apply("phone", "13812345678"), "13812345678");
+}
+
+#[test]
+fn masking_follows_role_and_key() {
+    let server = TestServer::start_with(|config| {
+        let mut agent = api_key("agent", "agent-key", &[Scope::Read]);
+        agent.role = Some("agent".to_string());
+        let mut auditor = api_key("auditor", "auditor-key", &[Scope::Read]);
+        auditor.role = Some("agent".to_string());
+        auditor.masking = Some(Masking::None);
+        let mut screen = api_key("screen", "screen-key", &[Scope::Read]);
+        screen.masking = Some(Masking::Full);
+        config.api_keys = vec![agent, auditor, screen];
+        config.roles.insert(
+            "agent".to_string(),
+            Role {
+                filterable: vec!["phone".to_string()],
+                visible: vec!["email".to_string(), "phone".to_string(), "qq".to_string()],
+                masking: Masking::Partial,
+            },
+        );
+    });
+
+    let json = server.get_as("agent-key", "/api/v1/users?phone=13800000001");
+    assert_eq!(json["items"][0]["phone"], "138****0001");
+    assert_eq!(json["items"][0]["email"], "a***@example.com");
+    assert_eq!(json["items"][0]["qq"], "10*01");
+    // 查询条件原样回显
+    assert_eq!(json["filters"]["phone"], "13800000001");
+
+    let json = server.get_as("agent-key", "/api/v1/users?phone=13800000002");
+    assert_eq!(json["items"][1]["qq"], serde_json::Value::Null);
+
+    let json = server.get_as("auditor-key", "/api/v1/users/1");
+    assert_eq!(json["phone"], "13800000001");
+
+    let json = server.get_as("screen-key", "/api/v1/users/1");
+    assert_eq!(json["phone"], "****");
+    assert_eq!(json["email"], "****");
+}
@@ -0,0 +1,136 @@
+mod common;
+
+use common::TestServer;
+use rusqlite::Connection;
+use userdata_rust::migrations::{self, MigrationError, LATEST_VERSION};
+
+#[test]
+fn server_creates_missing_database() {
+    let server = TestServer::start_with(|config| config.db_path = config.db_path.replace("user_data.db", "fresh.db"));
+
+    let json = server.get("/api/v1/stats").json();
+    assert_eq!(json["total_records"], 0);
+
+    let conn = Connection::open(&server.config.db_path).unwrap();
+    assert_eq!(migrations::user_version(&conn).unwrap(), LATEST_VERSION);
+}
+
+#[test]
+fn existing_database_is_upgraded_in_place() {
+    let server = TestServer::start();
+    let conn = Connection::open(&server.config.db_path).unwrap();
+    assert_eq!(migrations::user_version(&conn).unwrap(), LATEST_VERSION);
+
+    // 原 rowid 被保留为主键
+    assert_eq!(server.get("/api/v1/users/2").json()["email"], "bob@example.com");
+
+    let pk: String = conn
+        .query_row("SELECT name FROM pragma_table_info('users') WHERE pk = 1", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(pk, "id");
+
+    let indexes: i64 = conn
+        .query_row("SELECT COUNT(*) FROM pragma_index_list('users') WHERE name LIKE 'idx_users_%'", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(indexes, 3);
+
+    let created_at: Option<String> = conn
+        .query_row("SELECT created_at FROM users WHERE id = 1", [], |row| row.get(0))
+        .unwrap();
+    assert!(created_at.is_some());
+}
+
+#[test]
+fn migrations_are_idempotent_and_keep_extra_columns() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("extra.db").to_string_lossy().to_string();
+    Connection::open(&path)
+        .unwrap()
+        .execute_batch(
+            "CREATE TABLE users (email TEXT, phone TEXT, qq TEXT, nickname TEXT);
+             INSERT INTO users VALUES ('a@example.com', '13800000001', '10001', 'alice');",
+        )
+        .unwrap();
+
+    let report = migrations::run(&path, None).unwrap();
+    assert_eq!((report.from_version, report.to_version), (0, LATEST_VERSION));
+    let report = migrations::run(&path, None).unwrap();
+    assert_eq!((report.from_version, report.to_version), (LATEST_VERSION, LATEST_VERSION));
+
+    let nickname: String = Connection::open(&path)
+        .unwrap()
+        .query_row("SELECT nickname FROM users WHERE id = 1", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(nickname, "alice");
+}
+
+#[test]
+fn non_primary_key_id_column_is_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("id.db").to_string_lossy().to_string();
+    Connection::open(&path)
+        .unwrap()
+        .execute_batch("CREATE TABLE users (id TEXT, email TEXT, phone TEXT, qq TEXT);")
+        .unwrap();
+
+    assert!(matches!(migrations::run(&path, None), Err(MigrationError::UnsupportedSchema(_))));
+    // 失败的迁移不会留下部分修改
+    let conn = Connection::open(&path).unwrap();
+    assert_eq!(migrations::user_version(&conn).unwrap(), 1);
+}
+
+#[test]
+fn foreign_keys_and_generated_columns_are_not_dropped() {
+    let dir = tempfile::tempdir().unwrap();
+    for (name, sql, dropped) in [
+        (
+            "foreign_key.db",
+            "CREATE TABLE orgs (id INTEGER PRIMARY KEY);
+             CREATE TABLE users (email TEXT, phone TEXT, qq TEXT, org_id INTEGER REFERENCES orgs(id) ON DELETE CASCADE);",
+            "org_id REFERENCES orgs",
+        ),
+        (
+            "generated.db",
+            "CREATE TABLE users (email TEXT, phone TEXT, qq TEXT, domain TEXT GENERATED ALWAYS AS (substr(email, instr(email, '@') + 1)));",
+            "generated column domain",
+        ),
+    ] {
+        let path = dir.path().join(name).to_string_lossy().to_string();
+        Connection::open(&path).unwrap().execute_batch(sql).unwrap();
+
+        let Err(MigrationError::UnsupportedSchema(message)) = migrations::run(&path, None) else {
+            panic!("migration of {} should refuse to rebuild users", name);
+        };
+        assert_eq!(message, format!("rebuilding users would drop {}; migrate it manually", dropped));
+        assert_eq!(migrations::user_version(&Connection::open(&path).unwrap()).unwrap(), 1);
+    }
+}
+
+#[test]
+fn constraints_and_custom_indexes_are_not_dropped() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("constraints.db").to_string_lossy().to_string();
+    Connection::open(&path)
+        .unwrap()
+        .execute_batch(
+            "CREATE TABLE users (email TEXT NOT NULL, phone TEXT UNIQUE, qq TEXT);
+             CREATE INDEX users_by_qq ON users (qq);
+             INSERT INTO users VALUES ('a@example.com', '13800000001', '10001');",
+        )
+        .unwrap();
+
+    let Err(MigrationError::UnsupportedSchema(message)) = migrations::run(&path, None) else {
+        panic!("migration should refuse to rebuild users");
+    };
+    assert_eq!(
+        message,
+        "rebuilding users would drop email NOT NULL, UNIQUE constraint sqlite_autoindex_users_1, index users_by_qq; migrate it manually"
+    );
+
+    let conn = Connection::open(&path).unwrap();
+    assert_eq!(migrations::user_version(&conn).unwrap(), 1);
+    let index: i64 = conn
+        .query_row("SELECT COUNT(*) FROM sqlite_master WHERE name = 'users_by_qq'", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(index, 1);
+}
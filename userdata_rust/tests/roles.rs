@@ -0,0 +1,119 @@
+mod common;
+
+use common::{api_key, TestServer};
+use userdata_rust::access::Role;
+use userdata_rust::auth::Scope;
+use userdata_rust::{Server, ServerConfig, StartError};
+
+fn support_role() -> Role {
+    Role {
+        filterable: vec!["email".to_string()],
+        visible: vec!["email".to_string()],
+        ..Role::default()
+    }
+}
+
+fn support_server() -> TestServer {
+    TestServer::start_with(|config| {
+        let mut support = api_key("support", "support-key", &[Scope::Read, Scope::Write]);
+        support.role = Some("support".to_string());
+        config.api_keys = vec![support, api_key("full", "full-key", &[Scope::Read])];
+        config.roles.insert("support".to_string(), support_role());
+    })
+}
+
+#[test]
+fn role_limits_visible_fields() {
+    let server = support_server().with_api_key("support-key");
+
+    let json = server.get("/api/v1/users?email=bob@example.com").json();
+    let item = json["items"][0].as_object().unwrap();
+    assert_eq!(item["email"], "bob@example.com");
+    assert!(item.contains_key("id"));
+    assert!(!item.contains_key("phone"));
+    assert!(!item.contains_key("qq"));
+
+    let json = server.get("/api/v1/users/1").json();
+    assert_eq!(json["email"], "alice@example.com");
+    assert!(json.get("phone").is_none());
+
+    let json = server.post_form("/query", "email=carol@example.com").json();
+    assert!(json[0].get("phone").is_none());
+
+    // 写接口的返回值同样裁剪
+    let response = server.request(
+        "PATCH",
+        "/api/v1/users/1",
+        &[("Content-Type", "application/json")],
+        r#"{"email": "alice@example.org"}"#,
+    );
+    assert_eq!(response.status, 200);
+    assert_eq!(response.json()["email"], "alice@example.org");
+    assert!(response.json().get("qq").is_none());
+}
+
+#[test]
+fn role_limits_written_fields() {
+    let server = support_server().with_api_key("support-key");
+    let json = [("Content-Type", "application/json")];
+
+    let response = server.request("PATCH", "/api/v1/users/1", &json, r#"{"qq": "10009"}"#);
+    assert_eq!(response.status, 403);
+    assert_eq!(response.json()["error"]["code"], "forbidden_field");
+    assert_eq!(response.json()["error"]["message"], "Role 'support' may not write qq");
+    assert_eq!(server.request("POST", "/api/v1/users", &json, r#"{"email": "eve@example.com", "phone": "13800000009"}"#).status, 403);
+    // PUT 和 DELETE 会改动看不到的字段
+    assert_eq!(server.request("PUT", "/api/v1/users/1", &json, r#"{"email": "alice@example.com"}"#).status, 403);
+    assert_eq!(server.request("DELETE", "/api/v1/users/1", &[], "").status, 403);
+
+    assert_eq!(server.get_as("full-key", "/api/v1/users/1")["qq"], "10001");
+    assert_eq!(server.request("POST", "/api/v1/users", &json, r#"{"email": "eve@example.com"}"#).status, 201);
+}
+
+#[test]
+fn role_limits_filter_fields() {
+    let server = support_server().with_api_key("support-key");
+
+    for path in ["/api/v1/users?phone=13800000001", "/api/v1/users/qq/10001", "/api/v1/query?email=alice@example.com&phone=13800000001"] {
+        let response = server.get(path);
+        assert_eq!(response.status, 403, "path {}", path);
+        assert_eq!(response.json()["error"]["code"], "forbidden_filter");
+    }
+    assert_eq!(
+        server.get("/api/v1/users?phone=13800000001").json()["error"]["message"],
+        "Role 'support' may not filter on phone"
+    );
+}
+
+#[test]
+fn keys_without_role_see_everything() {
+    let server = support_server().with_api_key("full-key");
+    let json = server.get("/api/v1/users?phone=13800000001").json();
+    assert_eq!(json["items"][0]["qq"], "10001");
+    assert_eq!(json["items"][0]["email"], "alice@example.com");
+}
+
+#[test]
+fn invalid_roles_fail_startup() {
+    let mut key = api_key("support", "support-key", &[Scope::Read]);
+    key.role = Some("missing".to_string());
+    let config = ServerConfig {
+        port: 0,
+        api_keys: vec![key],
+        ..ServerConfig::default()
+    };
+    assert_eq!(config.validate().unwrap_err().to_string(), "Invalid config: API key 'support' refers to unknown role 'missing'");
+    let err = Server::start(config).err().unwrap();
+    assert!(matches!(err, StartError::Config(_)));
+    assert_eq!(err.code(), "invalid_config");
+
+    let mut config = ServerConfig::default();
+    config.roles.insert(
+        "typo".to_string(),
+        Role {
+            filterable: vec!["emial".to_string()],
+            ..Role::default()
+        },
+    );
+    assert_eq!(config.validate().unwrap_err().to_string(), "Invalid config: Role 'typo' names unknown fields: emial");
+}
@@ -0,0 +1,147 @@
+mod common;
+
+use common::TestServer;
+use rusqlite::Connection;
+use userdata_rust::migrations;
+use userdata_rust::schema::{self, SchemaError, SchemaMapping};
+
+const CUSTOM_SQL: &str = "
+    CREATE TABLE contacts (mail TEXT, mobile TEXT, \"qq number\" TEXT);
+    INSERT INTO contacts VALUES
+        ('alice@example.com', '13800000001', '10001'),
+        ('bob@example.com', '13800000002', '10002');";
+
+fn custom_mapping() -> SchemaMapping {
+    SchemaMapping {
+        table: "contacts".to_string(),
+        email: "mail".to_string(),
+        phone: "mobile".to_string(),
+        qq: "qq number".to_string(),
+    }
+}
+
+#[test]
+fn custom_mapping_serves_queries() {
+    let server = TestServer::start_with_sql(CUSTOM_SQL, |config| config.schema = custom_mapping());
+
+    let json = server.get("/api/v1/users?phone=13800000002").json();
+    assert_eq!(json["items"][0]["email"], "bob@example.com");
+    assert_eq!(json["items"][0]["qq"], "10002");
+
+    let json = server.get("/api/v1/stats").json();
+    assert_eq!(json["total_records"], 2);
+
+    // 自定义表结构不做迁移
+    let conn = Connection::open(&server.config.db_path).unwrap();
+    assert_eq!(migrations::user_version(&conn).unwrap(), 0);
+}
+
+#[test]
+fn validation_lists_missing_columns() {
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(CUSTOM_SQL).unwrap();
+
+    assert!(schema::validate(&conn, &custom_mapping()).is_ok());
+
+    let mapping = SchemaMapping {
+        phone: "phone".to_string(),
+        qq: "qq".to_string(),
+        ..custom_mapping()
+    };
+    let err = schema::validate(&conn, &mapping).unwrap_err();
+    assert!(matches!(&err, SchemaError::MissingColumns { columns, .. } if columns == &["phone", "qq"]));
+    assert_eq!(err.to_string(), "Table 'contacts' is missing columns: phone, qq");
+
+    let mapping = SchemaMapping::default();
+    assert!(matches!(schema::validate(&conn, &mapping), Err(SchemaError::MissingTable(table)) if table == "users"));
+}
+
+#[test]
+fn mapping_is_read_from_config_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    std::fs::write(&path, "db_path = \"/tmp/x.db\"\n[schema]\ntable = \"contacts\"\nemail = \"mail\"\n").unwrap();
+
+    let config = userdata_rust::ServerConfig::from_file(&path).unwrap();
+    assert_eq!(config.schema.table, "contacts");
+    assert_eq!(config.schema.email, "mail");
+    assert_eq!(config.schema.phone, "phone");
+}
+
+#[test]
+fn status_reports_missing_indexes() {
+    let server = TestServer::start_with_sql(CUSTOM_SQL, |config| config.schema = custom_mapping());
+
+    let json = server.get("/api/v1/status").json();
+    let indexes = json["indexes"].as_array().unwrap();
+    assert_eq!(indexes.len(), 3);
+    assert!(indexes.iter().all(|i| i["index"].is_null()));
+    assert_eq!(indexes[0]["field"], "phone");
+    assert_eq!(indexes[0]["column"], "mobile");
+}
+
+#[test]
+fn missing_indexes_are_created_when_enabled() {
+    let server = TestServer::start_with_sql(CUSTOM_SQL, |config| {
+        config.schema = custom_mapping();
+        config.create_missing_indexes = true;
+    });
+
+    let json = server.get("/api/v1/status").json();
+    let names = json["indexes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|i| i["index"].as_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["idx_contacts_mobile", "idx_contacts_qq_number", "idx_contacts_mail"]);
+}
+
+#[test]
+fn migrated_database_has_all_indexes() {
+    let server = TestServer::start();
+    let conn = Connection::open(&server.config.db_path).unwrap();
+    let statuses = schema::check_indexes(&conn, &SchemaMapping::default()).unwrap();
+    assert!(statuses.iter().all(|s| s.is_indexed()));
+}
+
+#[test]
+fn partial_and_secondary_indexes_do_not_count() {
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(
+        "CREATE TABLE users (email TEXT, phone TEXT, qq TEXT);
+         CREATE INDEX by_email_phone ON users (email, phone);
+         CREATE INDEX partial_qq ON users (qq) WHERE qq IS NOT NULL;",
+    )
+    .unwrap();
+
+    let statuses = schema::check_indexes(&conn, &SchemaMapping::default()).unwrap();
+    let indexed = statuses.iter().map(|s| (s.field, s.index.as_deref())).collect::<Vec<_>>();
+    assert_eq!(indexed, [("phone", None), ("qq", None), ("email", Some("by_email_phone"))]);
+}
+
+#[test]
+fn migrate_only_leaves_custom_schema_alone() {
+    let dir = tempfile::tempdir().unwrap();
+    let db_path = dir.path().join("contacts.db");
+    Connection::open(&db_path).unwrap().execute_batch(CUSTOM_SQL).unwrap();
+    let config_path = dir.path().join("config.toml");
+    std::fs::write(&config_path, "[schema]\ntable = \"contacts\"\nemail = \"mail\"\nphone = \"mobile\"\nqq = \"qq number\"\n").unwrap();
+
+    let output = std::process::Command::new(env!("CARGO_BIN_EXE_userdata-server"))
+        .arg("--config")
+        .arg(&config_path)
+        .arg("--db-path")
+        .arg(&db_path)
+        .arg("--migrate-only")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let conn = Connection::open(&db_path).unwrap();
+    assert_eq!(migrations::user_version(&conn).unwrap(), 0);
+    let tables: i64 = conn
+        .query_row("SELECT COUNT(*) FROM sqlite_master WHERE name = 'users'", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(tables, 0);
+}
@@ -0,0 +1,102 @@
+mod common;
+
+use common::{api_key, TestServer};
+use userdata_rust::auth::Scope;
+
+const TOKEN: &str = "test-write-key";
+
+fn writable_server() -> TestServer {
+    TestServer::start_with(|config| config.api_keys = vec![api_key("writer", TOKEN, &[Scope::Read, Scope::Write])])
+        .with_api_key(TOKEN)
+}
+
+fn send(server: &TestServer, method: &str, path: &str, body: &str) -> common::HttpResponse {
+    let auth = format!("Bearer {}", TOKEN);
+    server.request(method, path, &[("Authorization", &auth), ("Content-Type", "application/json")], body)
+}
+
+#[test]
+fn create_read_update_delete() {
+    let server = writable_server();
+
+    let response = send(&server, "POST", "/api/v1/users", r#"{"email": "dave@example.com", "phone": "13800000004"}"#);
+    assert_eq!(response.status, 201);
+    let created = response.json();
+    let id = created["id"].as_i64().unwrap();
+    assert_eq!(response.header("Location"), Some(format!("/api/v1/users/{}", id).as_str()));
+    assert_eq!(created["qq"], serde_json::Value::Null);
+
+    let fetched = server.get(&format!("/api/v1/users/{}", id)).json();
+    assert_eq!(fetched["email"], "dave@example.com");
+
+    let patched = send(&server, "PATCH", &format!("/api/v1/users/{}", id), r#"{"qq": "10004"}"#).json();
+    assert_eq!(patched["qq"], "10004");
+    assert_eq!(patched["phone"], "13800000004");
+
+    let replaced = send(&server, "PUT", &format!("/api/v1/users/{}", id), r#"{"phone": "13800000005"}"#).json();
+    assert_eq!(replaced["phone"], "13800000005");
+    assert_eq!(replaced["email"], serde_json::Value::Null);
+    assert_eq!(replaced["qq"], serde_json::Value::Null);
+
+    let json = server.get("/api/v1/users?phone=13800000005").json();
+    assert_eq!(json["items"][0]["id"], id);
+
+    assert_eq!(send(&server, "DELETE", &format!("/api/v1/users/{}", id), "").status, 204);
+    assert_eq!(server.get(&format!("/api/v1/users/{}", id)).status, 404);
+    assert_eq!(send(&server, "DELETE", &format!("/api/v1/users/{}", id), "").status, 404);
+}
+
+#[test]
+fn writes_require_key() {
+    let server = writable_server().without_api_key();
+    let body = r#"{"email": "eve@example.com"}"#;
+
+    let response = server.request("POST", "/api/v1/users", &[("Content-Type", "application/json")], body);
+    assert_eq!(response.status, 401);
+    assert_eq!(response.json()["error"]["code"], "unauthorized");
+
+    let response = server.request("DELETE", "/api/v1/users/1", &[("Authorization", "Bearer wrong")], "");
+    assert_eq!(response.status, 401);
+    assert_eq!(server.get_as(TOKEN, "/api/v1/users/1")["email"], "alice@example.com");
+}
+
+#[test]
+fn writes_are_disabled_without_keys() {
+    let server = TestServer::start_with(|config| config.api_keys.clear());
+    let response = server.request("DELETE", "/api/v1/users/1", &[("Authorization", "Bearer anything")], "");
+    assert_eq!(response.status, 403);
+    assert_eq!(response.json()["error"]["code"], "api_keys_required");
+}
+
+#[test]
+fn invalid_records_are_rejected() {
+    let server = writable_server();
+
+    for body in [r#"{}"#, r#"{"email": "not-an-email"}"#, r#"{"phone": "12ab"}"#, r#"{"qq": "0123"}"#] {
+        let response = send(&server, "POST", "/api/v1/users", body);
+        assert_eq!(response.status, 400, "body {}", body);
+        assert_eq!(response.json()["error"]["code"], "invalid_user", "body {}", body);
+    }
+
+    // 清空唯一的字段会导致记录为空
+    let response = send(&server, "PATCH", "/api/v1/users/3", r#"{"email": "", "phone": ""}"#);
+    assert_eq!(response.status, 400);
+    assert_eq!(server.get("/api/v1/users/3").json()["email"], "carol@example.com");
+}
+
+#[test]
+fn unknown_ids_are_not_found() {
+    let server = writable_server();
+    assert_eq!(send(&server, "PATCH", "/api/v1/users/999", r#"{"qq": "10009"}"#).status, 404);
+    assert_eq!(server.get("/api/v1/users/abc").status, 404);
+}
+
+#[test]
+fn writes_require_write_scope() {
+    let server = TestServer::start_with(|config| config.api_keys = vec![api_key("reader", "read-key", &[Scope::Read])])
+        .with_api_key("read-key");
+    let response = server.request("DELETE", "/api/v1/users/1", &[("Authorization", "Bearer read-key")], "");
+    assert_eq!(response.status, 403);
+    assert_eq!(response.json()["error"]["code"], "insufficient_scope");
+    assert_eq!(server.get("/api/v1/users/1").status, 200);
+}